@@ -0,0 +1,76 @@
+//! Compares allocations made while writing a deeply nested document through
+//! `Display` into a `String` against streaming it with `save_to_writer`, and
+//! with `save_to_file`, which shouldn't copy the document either.
+//!
+//! Run with `cargo bench --bench serialize`.
+
+use std::{
+    alloc::{GlobalAlloc, Layout, System},
+    io::{self, Write},
+    sync::atomic::{AtomicUsize, Ordering},
+    time::Instant,
+};
+
+use cool::prelude::*;
+
+struct CountingAlloc;
+
+static ALLOCATIONS: AtomicUsize = AtomicUsize::new(0);
+static ALLOCATED_BYTES: AtomicUsize = AtomicUsize::new(0);
+
+unsafe impl GlobalAlloc for CountingAlloc {
+    unsafe fn alloc(&self, layout: Layout) -> *mut u8 {
+        ALLOCATIONS.fetch_add(1, Ordering::Relaxed);
+        ALLOCATED_BYTES.fetch_add(layout.size(), Ordering::Relaxed);
+        System.alloc(layout)
+    }
+
+    unsafe fn dealloc(&self, ptr: *mut u8, layout: Layout) {
+        System.dealloc(ptr, layout)
+    }
+}
+
+#[global_allocator]
+static GLOBAL: CountingAlloc = CountingAlloc;
+
+/// An object nested `depth` levels deep, with a few fields on every level.
+fn document(depth: usize) -> CoolDataObject {
+    let mut object = CoolDataObject::new();
+    for level in (0..depth).rev() {
+        let mut outer = CoolDataObject::new();
+        outer.add_field("name".into(), format!("level {}", level).into());
+        outer.add_field("ports".into(), vec![80, 443].into());
+        outer.add_field("inner".into(), object.into());
+        object = outer;
+    }
+    object
+}
+
+fn measure(name: &str, f: impl Fn()) {
+    let before = ALLOCATIONS.load(Ordering::Relaxed);
+    let before_bytes = ALLOCATED_BYTES.load(Ordering::Relaxed);
+    let start = Instant::now();
+    f();
+    let elapsed = start.elapsed();
+    let allocations = ALLOCATIONS.load(Ordering::Relaxed) - before;
+    let bytes = ALLOCATED_BYTES.load(Ordering::Relaxed) - before_bytes;
+    println!(
+        "{:<10} {:>8} allocations {:>12} bytes {:>10.2?}",
+        name, allocations, bytes, elapsed
+    );
+}
+
+fn main() {
+    let object = document(100);
+
+    measure("display", || {
+        let text = object.to_string();
+        io::sink().write_all(text.as_bytes()).unwrap();
+    });
+    measure("streaming", || save_to_writer(io::sink(), &object).unwrap());
+
+    let path = std::env::temp_dir().join("cool-serialize-bench.cool");
+    let path = path.to_str().unwrap();
+    measure("file", || save_to_file(path, &object).unwrap());
+    std::fs::remove_file(path).unwrap();
+}
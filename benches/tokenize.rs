@@ -0,0 +1,86 @@
+//! Compares allocations made while tokenizing with borrowed token text against
+//! copying every token into an owned one, as the lexer used to.
+//!
+//! Run with `cargo bench --bench tokenize`.
+
+use std::{
+    alloc::{GlobalAlloc, Layout, System},
+    sync::atomic::{AtomicUsize, Ordering},
+    time::Instant,
+};
+
+use cool::lexer::{Token, Tokenizer};
+
+struct CountingAlloc;
+
+static ALLOCATIONS: AtomicUsize = AtomicUsize::new(0);
+
+unsafe impl GlobalAlloc for CountingAlloc {
+    unsafe fn alloc(&self, layout: Layout) -> *mut u8 {
+        ALLOCATIONS.fetch_add(1, Ordering::Relaxed);
+        System.alloc(layout)
+    }
+
+    unsafe fn dealloc(&self, ptr: *mut u8, layout: Layout) {
+        System.dealloc(ptr, layout)
+    }
+}
+
+#[global_allocator]
+static GLOBAL: CountingAlloc = CountingAlloc;
+
+/// `i` spelled in letters, since identifiers can't contain digits.
+fn key(mut i: usize) -> String {
+    let mut letters = Vec::new();
+    loop {
+        letters.push(b'a' + (i % 26) as u8);
+        i /= 26;
+        if i == 0 {
+            break;
+        }
+    }
+    letters.reverse();
+    format!("field_{}", String::from_utf8(letters).unwrap())
+}
+
+fn document(fields: usize) -> String {
+    let mut out = String::new();
+    for i in 0..fields {
+        out.push_str(&format!(
+            "{} = {{\nname = \"value {}\"\nratio = {}.5\nports = [80, 443]\n}}\n",
+            key(i),
+            i,
+            i
+        ));
+    }
+    out
+}
+
+fn measure<T>(name: &str, f: impl Fn() -> T) {
+    let before = ALLOCATIONS.load(Ordering::Relaxed);
+    let start = Instant::now();
+    let result = f();
+    let elapsed = start.elapsed();
+    let allocations = ALLOCATIONS.load(Ordering::Relaxed) - before;
+    drop(result);
+    println!(
+        "{:<10} {:>8} allocations {:>10.2?}",
+        name, allocations, elapsed
+    );
+}
+
+fn main() {
+    let content = document(10_000);
+    assert_eq!(
+        cool::parse(content.as_str()).unwrap().iter().count(),
+        10_000
+    );
+
+    measure("borrowed", || Tokenizer::new(&content).tokenize().unwrap());
+    measure("owned", || {
+        Tokenizer::new(&content)
+            .map(|token| token.map(Token::into_owned))
+            .collect::<std::io::Result<Vec<_>>>()
+            .unwrap()
+    });
+}
@@ -0,0 +1,100 @@
+//! Derive macros for the `cool` crate, re-exported from it behind its `derive` feature.
+
+use proc_macro::TokenStream;
+use proc_macro2::TokenStream as TokenStream2;
+use quote::quote;
+use syn::{parse_macro_input, Data, DeriveInput, Fields, Ident};
+
+/// Collects the named fields of a struct, or a compile error for anything else.
+fn named_fields(input: &DeriveInput) -> Result<Vec<(Ident, String)>, TokenStream2> {
+    let Data::Struct(data) = &input.data else {
+        return Err(
+            syn::Error::new_spanned(input, "Only structs can be derived").to_compile_error(),
+        );
+    };
+    let Fields::Named(fields) = &data.fields else {
+        return Err(syn::Error::new_spanned(
+            input,
+            "Only structs with named fields can be derived",
+        )
+        .to_compile_error());
+    };
+
+    Ok(fields
+        .named
+        .iter()
+        .map(|field| {
+            let ident = field.ident.clone().unwrap();
+            let name = ident.to_string().trim_start_matches("r#").to_string();
+            (ident, name)
+        })
+        .collect())
+}
+
+/// Implements `cool::typed::CoolDeserialize` and `FromCool`, reading each field
+/// from the object field of the same name.
+#[proc_macro_derive(CoolDeserialize)]
+pub fn derive_deserialize(input: TokenStream) -> TokenStream {
+    let input = parse_macro_input!(input as DeriveInput);
+    let fields = match named_fields(&input) {
+        Ok(fields) => fields,
+        Err(err) => return err.into(),
+    };
+    let name = &input.ident;
+    let (impl_generics, ty_generics, where_clause) = input.generics.split_for_impl();
+    let reads = fields.iter().map(|(ident, key)| {
+        quote! { #ident: ::cool::typed::FromCool::from_cool_field(object, #key)? }
+    });
+
+    quote! {
+        impl #impl_generics ::cool::typed::CoolDeserialize for #name #ty_generics #where_clause {
+            fn from_cool(object: &::cool::parser::CoolDataObject) -> ::std::io::Result<Self> {
+                Ok(Self { #(#reads,)* })
+            }
+        }
+
+        impl #impl_generics ::cool::typed::FromCool for #name #ty_generics #where_clause {
+            fn from_cool_value(value: &::cool::parser::CoolDataType) -> ::std::io::Result<Self> {
+                <Self as ::cool::typed::CoolDeserialize>::from_cool(
+                    ::cool::typed::expect_object(value)?,
+                )
+            }
+        }
+    }
+    .into()
+}
+
+/// Implements `cool::typed::CoolSerialize` and `ToCool`, writing each field as an
+/// object field of the same name.
+#[proc_macro_derive(CoolSerialize)]
+pub fn derive_serialize(input: TokenStream) -> TokenStream {
+    let input = parse_macro_input!(input as DeriveInput);
+    let fields = match named_fields(&input) {
+        Ok(fields) => fields,
+        Err(err) => return err.into(),
+    };
+    let name = &input.ident;
+    let (impl_generics, ty_generics, where_clause) = input.generics.split_for_impl();
+    let writes = fields.iter().map(|(ident, key)| {
+        quote! { ::cool::typed::ToCool::to_cool_field(&self.#ident, &mut object, #key); }
+    });
+
+    quote! {
+        impl #impl_generics ::cool::typed::CoolSerialize for #name #ty_generics #where_clause {
+            fn to_cool(&self) -> ::cool::parser::CoolDataObject {
+                let mut object = ::cool::parser::CoolDataObject::new();
+                #(#writes)*
+                object
+            }
+        }
+
+        impl #impl_generics ::cool::typed::ToCool for #name #ty_generics #where_clause {
+            fn to_cool_value(&self) -> ::cool::parser::CoolDataType {
+                ::cool::parser::CoolDataType::Object(
+                    <Self as ::cool::typed::CoolSerialize>::to_cool(self),
+                )
+            }
+        }
+    }
+    .into()
+}
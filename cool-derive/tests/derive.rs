@@ -0,0 +1,54 @@
+use cool::prelude::*;
+use std::io::Result;
+
+#[derive(Debug, PartialEq, CoolDeserialize, CoolSerialize)]
+struct Server {
+    host: String,
+    port: i32,
+}
+
+#[derive(Debug, PartialEq, CoolDeserialize, CoolSerialize)]
+struct Config {
+    name: String,
+    debug: bool,
+    server: Server,
+    tags: Vec<String>,
+    timeout: Option<f32>,
+    r#type: Option<char>,
+}
+
+#[test]
+fn round_trip() -> Result<()> {
+    let object = parse(
+        "name = \"app\"\ndebug = false\nserver = {\nhost = \"localhost\"\nport = 8080\n}\ntags = [\"a\", \"b\"]\ntype = 'x'\n",
+    )?;
+
+    let config = Config::from_cool(&object)?;
+    assert_eq!(
+        config,
+        Config {
+            name: "app".to_string(),
+            debug: false,
+            server: Server {
+                host: "localhost".to_string(),
+                port: 8080,
+            },
+            tags: vec!["a".to_string(), "b".to_string()],
+            timeout: None,
+            r#type: Some('x'),
+        }
+    );
+    assert_eq!(config.to_cool(), object);
+    Ok(())
+}
+
+#[test]
+fn errors_name_the_field() -> Result<()> {
+    let object = parse("host = \"localhost\"\nport = \"80\"\n")?;
+    let err = Server::from_cool(&object).unwrap_err();
+    assert_eq!(err.to_string(), "Field \"port\": Expected int, got string.");
+
+    let object = parse("host = \"localhost\"\n")?;
+    assert!(Server::from_cool(&object).is_err());
+    Ok(())
+}
@@ -0,0 +1,145 @@
+use quickcheck::{Arbitrary, Gen};
+
+use crate::parser::*;
+
+/// Nesting depth generated values stop at.
+const MAX_DEPTH: usize = 3;
+
+/// Characters for generated strings, keys and chars.
+const CHARS: &[char] = &[
+    'a', 'b', 'z', 'A', 'Z', '0', '9', '_', '-', '.', ' ', ',', '=', '{', ']', '#', 'é', 'ß', '中',
+    '🦀',
+];
+
+/// Characters that need escaping in strings, keys or chars.
+const ESCAPED_CHARS: &[char] = &['"', '\'', '\\', '\t', '\r', '\0'];
+
+fn arbitrary_char(g: &mut Gen) -> char {
+    match u8::arbitrary(g) % 8 {
+        0 => *g.choose(ESCAPED_CHARS).unwrap(),
+        _ => *g.choose(CHARS).unwrap(),
+    }
+}
+
+fn arbitrary_string(g: &mut Gen) -> String {
+    let len = usize::arbitrary(g) % 8;
+    (0..len).map(|_| arbitrary_char(g)).collect()
+}
+
+fn arbitrary_text(g: &mut Gen) -> String {
+    let len = usize::arbitrary(g) % 8;
+    (0..len)
+        .map(|_| match u8::arbitrary(g) % 8 {
+            0 => '\n',
+            _ => arbitrary_char(g),
+        })
+        .collect()
+}
+
+fn arbitrary_float(g: &mut Gen) -> f32 {
+    match f32::arbitrary(g) {
+        val if val.is_nan() => 0.5,
+        val => val,
+    }
+}
+
+fn arbitrary_value(g: &mut Gen, depth: usize) -> CoolDataType {
+    let kinds = if depth < MAX_DEPTH { 10 } else { 8 };
+    match usize::arbitrary(g) % kinds {
+        0 => CoolDataType::Int(i64::arbitrary(g)),
+        1 => CoolDataType::UInt(u64::arbitrary(g)),
+        2 => CoolDataType::Float(arbitrary_float(g)),
+        3 => CoolDataType::String(arbitrary_text(g)),
+        4 => CoolDataType::Bool(bool::arbitrary(g)),
+        5 => CoolDataType::Char(arbitrary_char(g)),
+        #[cfg(feature = "base64")]
+        6 => CoolDataType::Bytes(Vec::arbitrary(g)),
+        #[cfg(not(feature = "base64"))]
+        6 => CoolDataType::Null,
+        7 => CoolDataType::Null,
+        8 => CoolDataType::List(arbitrary_list(g, depth + 1)),
+        _ => CoolDataType::Object(arbitrary_object(g, depth + 1)),
+    }
+}
+
+fn arbitrary_list(g: &mut Gen, depth: usize) -> CoolDataList {
+    let len = usize::arbitrary(g) % 5;
+    CoolDataList((0..len).map(|_| arbitrary_value(g, depth)).collect())
+}
+
+fn arbitrary_object(g: &mut Gen, depth: usize) -> CoolDataObject {
+    let len = usize::arbitrary(g) % 5;
+    let mut out = CoolDataObject::new();
+    for _ in 0..len {
+        out.add_field(arbitrary_string(g), arbitrary_value(g, depth));
+    }
+    out
+}
+
+/// NaN is never generated, since it wouldn't compare equal after a round trip.
+impl Arbitrary for CoolDataType {
+    fn arbitrary(g: &mut Gen) -> Self {
+        arbitrary_value(g, 0)
+    }
+
+    fn shrink(&self) -> Box<dyn Iterator<Item = Self>> {
+        match self {
+            CoolDataType::Object(val) => Box::new(val.shrink().map(CoolDataType::Object)),
+            CoolDataType::List(val) => Box::new(val.shrink().map(CoolDataType::List)),
+            CoolDataType::Null => quickcheck::empty_shrinker(),
+            _ => quickcheck::single_shrinker(CoolDataType::Null),
+        }
+    }
+}
+
+impl Arbitrary for CoolDataList {
+    fn arbitrary(g: &mut Gen) -> Self {
+        arbitrary_list(g, 0)
+    }
+
+    fn shrink(&self) -> Box<dyn Iterator<Item = Self>> {
+        Box::new(self.0.shrink().map(CoolDataList))
+    }
+}
+
+impl Arbitrary for CoolDataObject {
+    fn arbitrary(g: &mut Gen) -> Self {
+        arbitrary_object(g, 0)
+    }
+
+    /// Shrinks by dropping one field at a time, then by shrinking single values.
+    fn shrink(&self) -> Box<dyn Iterator<Item = Self>> {
+        let fields: Vec<(String, CoolDataType)> = self
+            .iter()
+            .map(|(key, value)| (key.clone(), value.clone()))
+            .collect();
+        let without = (0..fields.len()).map({
+            let fields = fields.clone();
+            move |skip| {
+                let mut out = CoolDataObject::new();
+                for (i, (key, value)) in fields.iter().enumerate() {
+                    if i != skip {
+                        out.add_field(key.clone(), value.clone());
+                    }
+                }
+                out
+            }
+        });
+        let shrunk = (0..fields.len()).flat_map(move |index| {
+            let fields = fields.clone();
+            fields[index].1.shrink().map(move |value| {
+                let mut out = CoolDataObject::new();
+                for (i, (key, original)) in fields.iter().enumerate() {
+                    let value = if i == index {
+                        value.clone()
+                    } else {
+                        original.clone()
+                    };
+                    out.add_field(key.clone(), value);
+                }
+                out
+            })
+        });
+        Box::new(without.chain(shrunk))
+    }
+}
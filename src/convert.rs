@@ -0,0 +1,100 @@
+use std::io::{Error, ErrorKind, Result};
+
+use crate::parser::*;
+
+macro_rules! impl_try_from_ref {
+    ($type:ty, $($data_type:ident)|+) => {
+        impl TryFrom<&CoolDataType> for $type {
+            type Error = Error;
+
+            fn try_from(value: &CoolDataType) -> Result<Self> {
+                match value {
+                    $(CoolDataType::$data_type(val) => val.clone().try_into().map_err(|_| {
+                        Error::new(
+                            ErrorKind::InvalidInput,
+                            format!("Value is out of range for {}.", stringify!($type)),
+                        )
+                    }),)+
+                    _ => Err(Error::new(
+                        ErrorKind::InvalidInput,
+                        format!("Value is not a {}.", stringify!($type)),
+                    )),
+                }
+            }
+        }
+    };
+}
+
+impl_try_from_ref!(i32, Int);
+impl_try_from_ref!(i64, Int);
+impl_try_from_ref!(u64, UInt);
+impl_try_from_ref!(f32, Float);
+impl_try_from_ref!(f64, Float);
+impl_try_from_ref!(String, String);
+impl_try_from_ref!(bool, Bool);
+impl_try_from_ref!(char, Char);
+
+macro_rules! impl_try_from_owned {
+    ($type:ty, $($data_type:ident)|+) => {
+        impl TryFrom<CoolDataType> for $type {
+            type Error = Error;
+
+            fn try_from(value: CoolDataType) -> Result<Self> {
+                match value {
+                    $(CoolDataType::$data_type(val) => val.try_into().map_err(|_| {
+                        Error::new(
+                            ErrorKind::InvalidInput,
+                            format!("Value is out of range for {}.", stringify!($type)),
+                        )
+                    }),)+
+                    _ => Err(Error::new(
+                        ErrorKind::InvalidInput,
+                        format!("Value is not a {}.", stringify!($type)),
+                    )),
+                }
+            }
+        }
+    };
+}
+
+impl_try_from_owned!(i32, Int);
+impl_try_from_owned!(i64, Int);
+impl_try_from_owned!(u64, UInt);
+impl_try_from_owned!(f32, Float);
+impl_try_from_owned!(f64, Float);
+impl_try_from_owned!(String, String);
+impl_try_from_owned!(bool, Bool);
+impl_try_from_owned!(char, Char);
+impl_try_from_owned!(CoolDataObject, Object);
+impl_try_from_owned!(CoolDataList, List);
+
+macro_rules! impl_try_from_list {
+    ($len:literal; $($name:ident: $index:tt),+) => {
+        /// Converts a list of exactly this many elements, erroring on a length or element type mismatch.
+        impl<$($name),+> TryFrom<&CoolDataList> for ($($name,)+)
+        where
+            $($name: for<'a> TryFrom<&'a CoolDataType, Error = Error>,)+
+        {
+            type Error = Error;
+
+            fn try_from(list: &CoolDataList) -> Result<Self> {
+                if list.0.len() != $len {
+                    return Err(Error::new(
+                        ErrorKind::InvalidInput,
+                        format!("Expected a list of {} elements, got {}.", $len, list.0.len()),
+                    ));
+                }
+                Ok(($(
+                    $name::try_from(&list.0[$index]).map_err(|err| {
+                        Error::new(err.kind(), format!("Index {}: {}", $index, err))
+                    })?,
+                )+))
+            }
+        }
+    };
+}
+
+impl_try_from_list!(1; A: 0);
+impl_try_from_list!(2; A: 0, B: 1);
+impl_try_from_list!(3; A: 0, B: 1, C: 2);
+impl_try_from_list!(4; A: 0, B: 1, C: 2, D: 3);
@@ -0,0 +1,95 @@
+use crate::parser::*;
+
+/// A single difference between two objects, see [`CoolDataObject::diff`].
+#[derive(Debug, Clone, PartialEq)]
+#[allow(clippy::large_enum_variant)]
+pub enum Change {
+    Added {
+        path: String,
+        value: CoolDataType,
+    },
+    Removed {
+        path: String,
+        value: CoolDataType,
+    },
+    Changed {
+        path: String,
+        from: CoolDataType,
+        to: CoolDataType,
+    },
+}
+
+impl CoolDataObject {
+    /// Lists what changed from `self` to `other`, with dotted paths into nested
+    /// values. Objects are compared by key and lists element-wise by index.
+    pub fn diff(&self, other: &CoolDataObject) -> Vec<Change> {
+        let mut changes = Vec::new();
+        diff_objects(&mut changes, None, self, other);
+        changes
+    }
+}
+
+fn join(prefix: Option<&str>, segment: &str) -> String {
+    match prefix {
+        Some(prefix) => format!("{}.{}", prefix, segment),
+        None => segment.to_string(),
+    }
+}
+
+fn diff_objects(
+    changes: &mut Vec<Change>,
+    prefix: Option<&str>,
+    from: &CoolDataObject,
+    to: &CoolDataObject,
+) {
+    for (key, value) in from.iter() {
+        let path = join(prefix, key);
+        match to.as_map().get(key) {
+            Some(other) => diff_values(changes, path, value, other),
+            None => changes.push(Change::Removed {
+                path,
+                value: value.clone(),
+            }),
+        }
+    }
+    for (key, value) in to.iter() {
+        if !from.as_map().contains_key(key) {
+            changes.push(Change::Added {
+                path: join(prefix, key),
+                value: value.clone(),
+            });
+        }
+    }
+}
+
+fn diff_values(changes: &mut Vec<Change>, path: String, from: &CoolDataType, to: &CoolDataType) {
+    match (from, to) {
+        (CoolDataType::Object(from), CoolDataType::Object(to)) => {
+            diff_objects(changes, Some(&path), from, to)
+        }
+        (CoolDataType::List(from), CoolDataType::List(to)) => {
+            for (index, value) in from.0.iter().enumerate() {
+                let path = format!("{}.{}", path, index);
+                match to.0.get(index) {
+                    Some(other) => diff_values(changes, path, value, other),
+                    None => changes.push(Change::Removed {
+                        path,
+                        value: value.clone(),
+                    }),
+                }
+            }
+            for (index, value) in to.0.iter().enumerate().skip(from.0.len()) {
+                changes.push(Change::Added {
+                    path: format!("{}.{}", path, index),
+                    value: value.clone(),
+                });
+            }
+        }
+        _ if from != to => changes.push(Change::Changed {
+            path,
+            from: from.clone(),
+            to: to.clone(),
+        }),
+        _ => {}
+    }
+}
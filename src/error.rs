@@ -0,0 +1,69 @@
+use std::{
+    fmt::{self, Display},
+    io::{Error, ErrorKind},
+};
+
+use crate::lexer::Loc;
+
+/// Details of a failure, carried inside the `io::Error` the crate returns and
+/// retrieved with [`CoolError::of`]. Unlike `io::Error` it's `Clone` and
+/// `PartialEq`, so tests can compare errors. Getters such as
+/// [`CoolDataObject::get_int`] use it to tell a field to default apart from a
+/// malformed one.
+///
+/// [`CoolDataObject::get_int`]: crate::parser::CoolDataObject::get_int
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum CoolError {
+    FieldMissing {
+        name: String,
+    },
+    TypeMismatch {
+        name: String,
+        expected: &'static str,
+        found: &'static str,
+    },
+    /// The tokenizer or parser failed at `loc`, which is just past the last token
+    /// when the input ended early. `message` is the full text of the error,
+    /// location and any source snippet included.
+    Parse {
+        kind: ErrorKind,
+        message: String,
+        loc: Loc,
+    },
+}
+
+impl CoolError {
+    /// The `CoolError` behind `err`, if it has one.
+    pub fn of(err: &Error) -> Option<&CoolError> {
+        err.get_ref()?.downcast_ref()
+    }
+
+    pub fn kind(&self) -> ErrorKind {
+        match self {
+            CoolError::FieldMissing { .. } | CoolError::TypeMismatch { .. } => {
+                ErrorKind::InvalidInput
+            }
+            CoolError::Parse { kind, .. } => *kind,
+        }
+    }
+}
+
+impl Display for CoolError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            CoolError::FieldMissing { name } => write!(f, "Unknown field {:?}", name),
+            CoolError::TypeMismatch { name, expected, .. } => {
+                write!(f, "Field {:?} is not a {}.", name, expected)
+            }
+            CoolError::Parse { message, .. } => write!(f, "{}", message),
+        }
+    }
+}
+
+impl std::error::Error for CoolError {}
+
+impl From<CoolError> for Error {
+    fn from(err: CoolError) -> Self {
+        Error::new(err.kind(), err)
+    }
+}
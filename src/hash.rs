@@ -0,0 +1,94 @@
+use std::{
+    hash::{Hash, Hasher},
+    io::{Error, ErrorKind, Result},
+    mem,
+};
+
+use crate::parser::*;
+
+/// A value usable as a `HashMap` or `HashSet` key.
+///
+/// Floats aren't `Eq` (NaN isn't equal to itself), so values containing a
+/// `Float` anywhere, nested ones included, are rejected by [`HashKey::new`].
+/// Objects hash independently of field order, matching their `PartialEq`.
+#[derive(Debug, Clone, PartialEq)]
+pub struct HashKey(CoolDataType);
+
+impl HashKey {
+    /// Wraps `value`, or errors if it contains a float.
+    pub fn new(value: impl Into<CoolDataType>) -> Result<Self> {
+        let value = value.into();
+        if contains_float(&value) {
+            return Err(Error::new(
+                ErrorKind::InvalidInput,
+                "Values containing a float can't be hashed.",
+            ));
+        }
+        Ok(Self(value))
+    }
+
+    pub fn value(&self) -> &CoolDataType {
+        &self.0
+    }
+
+    pub fn into_inner(self) -> CoolDataType {
+        self.0
+    }
+}
+
+fn contains_float(value: &CoolDataType) -> bool {
+    match value {
+        CoolDataType::Float(_) => true,
+        CoolDataType::Object(val) => val.iter().any(|(_, value)| contains_float(value)),
+        CoolDataType::List(val) => val.0.iter().any(contains_float),
+        _ => false,
+    }
+}
+
+fn hash_value<H: Hasher>(value: &CoolDataType, state: &mut H) {
+    mem::discriminant(value).hash(state);
+    match value {
+        CoolDataType::Int(val) => val.hash(state),
+        CoolDataType::UInt(val) => val.hash(state),
+        #[cfg(feature = "bigint")]
+        CoolDataType::BigInt(val) => val.hash(state),
+        CoolDataType::String(val) => val.hash(state),
+        CoolDataType::Bool(val) => val.hash(state),
+        CoolDataType::Char(val) => val.hash(state),
+        #[cfg(feature = "base64")]
+        CoolDataType::Bytes(val) => val.hash(state),
+        CoolDataType::Object(val) => {
+            let mut fields: Vec<_> = val.iter().collect();
+            fields.sort_by(|a, b| a.0.cmp(b.0));
+            fields.len().hash(state);
+            for (key, value) in fields {
+                key.hash(state);
+                hash_value(value, state);
+            }
+        }
+        CoolDataType::List(val) => {
+            val.0.len().hash(state);
+            for value in val.0.iter() {
+                hash_value(value, state);
+            }
+        }
+        CoolDataType::Float(_) => unreachable!("rejected by HashKey::new"),
+        CoolDataType::Null => {}
+    }
+}
+
+impl Eq for HashKey {}
+
+impl Hash for HashKey {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        hash_value(&self.0, state);
+    }
+}
+
+impl TryFrom<CoolDataType> for HashKey {
+    type Error = Error;
+
+    fn try_from(value: CoolDataType) -> Result<Self> {
+        Self::new(value)
+    }
+}
@@ -0,0 +1,85 @@
+use std::io::{Error, ErrorKind, Result};
+
+use crate::parser::*;
+
+impl CoolDataObject {
+    /// Expands `${VAR}` in every string value from the process environment, see
+    /// [`CoolDataObject::interpolate_with`].
+    pub fn interpolate_env(&mut self) -> Result<()> {
+        self.interpolate_with(|name| std::env::var(name).ok())
+    }
+
+    /// Expands `${VAR}` in every string value, recursively, with the value `lookup`
+    /// returns for `VAR`. `${VAR:-fallback}` uses `fallback` when `VAR` is
+    /// undefined, `$$` is a literal `$`, and an undefined variable without a
+    /// fallback is an error.
+    pub fn interpolate_with(&mut self, lookup: impl Fn(&str) -> Option<String>) -> Result<()> {
+        interpolate_object(self, &lookup)
+    }
+}
+
+fn interpolate_object(
+    object: &mut CoolDataObject,
+    lookup: &impl Fn(&str) -> Option<String>,
+) -> Result<()> {
+    let keys: Vec<String> = object.iter().map(|(key, _)| key.clone()).collect();
+    for key in keys {
+        interpolate_value(object.get_field_mut(&key)?, lookup)?;
+    }
+    Ok(())
+}
+
+fn interpolate_value(
+    value: &mut CoolDataType,
+    lookup: &impl Fn(&str) -> Option<String>,
+) -> Result<()> {
+    match value {
+        CoolDataType::String(val) => *val = interpolate(val, lookup)?,
+        CoolDataType::Object(val) => interpolate_object(val, lookup)?,
+        CoolDataType::List(val) => {
+            for value in val.0.iter_mut() {
+                interpolate_value(value, lookup)?;
+            }
+        }
+        _ => {}
+    }
+    Ok(())
+}
+
+fn interpolate(text: &str, lookup: &impl Fn(&str) -> Option<String>) -> Result<String> {
+    let mut out = String::with_capacity(text.len());
+    let mut rest = text;
+
+    while let Some(start) = rest.find('$') {
+        out.push_str(&rest[..start]);
+        rest = &rest[start..];
+
+        if let Some(after) = rest.strip_prefix("$$") {
+            out.push('$');
+            rest = after;
+        } else if let Some(after) = rest.strip_prefix("${") {
+            let end = after.find('}').ok_or(Error::new(
+                ErrorKind::InvalidData,
+                format!("Unclosed `${{` in {:?}", text),
+            ))?;
+            let (name, default) = match after[..end].split_once(":-") {
+                Some((name, default)) => (name, Some(default)),
+                None => (&after[..end], None),
+            };
+            let value = lookup(name)
+                .or(default.map(String::from))
+                .ok_or(Error::new(
+                    ErrorKind::NotFound,
+                    format!("Undefined environment variable {:?}", name),
+                ))?;
+            out.push_str(&value);
+            rest = &after[end + 1..];
+        } else {
+            out.push('$');
+            rest = &rest[1..];
+        }
+    }
+    out.push_str(rest);
+
+    Ok(out)
+}
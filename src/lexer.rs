@@ -1,44 +1,200 @@
 use std::{
+    borrow::Cow,
+    collections::HashSet,
     fmt::Display,
     io::{Error, ErrorKind, Result},
+    ops::Range,
 };
 
+use crate::error::CoolError;
+
+/// Kind of a token. Text payloads borrow from the source where possible.
 #[derive(Debug, Clone)]
-pub enum TokenType {
-    Ident(String),
+pub enum TokenType<'a> {
+    Ident(Cow<'a, str>),
+    /// An identifier listed in [`TokenizerOptions::allowed_keywords`].
+    Keyword(Cow<'a, str>),
     Bool(bool),
+    Char(char),
+    /// A `b64"..."` literal, already decoded.
+    #[cfg(feature = "base64")]
+    Bytes(Vec<u8>),
+    Null,
     Equals,
-    String(String),
-    Int(String),
-    Float(String),
+    Colon,
+    String(Cow<'a, str>),
+    Int(Cow<'a, str>),
+    Float(Cow<'a, str>),
     LeftBrace,
     RightBrace,
     LeftBracket,
     RightBracket,
     Comma,
     Newline,
+    /// A `@name` directive such as `@include`, holding the name without the `@`.
+    Directive(Cow<'a, str>),
+    /// A `#` comment, holding its text with the `#` and surrounding whitespace removed.
+    Comment(Cow<'a, str>),
+    /// A `---` line outside any braces or brackets, separating concatenated documents.
+    DocumentEnd,
 }
 
-#[derive(Debug, Clone)]
-/// Location of a token in form (col, line).
+#[derive(Debug, Clone, PartialEq, Eq)]
+/// Location of a token in form (col, line). Prefer [`Loc::new`] and the named
+/// accessors, which take and return line first like the `Display` output.
 pub struct Loc(pub usize, pub usize);
 
+impl Loc {
+    pub fn new(line: usize, col: usize) -> Self {
+        Self(col, line)
+    }
+
+    pub fn line(&self) -> usize {
+        self.1
+    }
+
+    pub fn col(&self) -> usize {
+        self.0
+    }
+}
+
 impl Display for Loc {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
-        write!(f, "{}:{}", self.1, self.0)
+        write!(f, "{}:{}", self.line(), self.col())
+    }
+}
+
+#[derive(Debug, Clone)]
+/// Source range of a token. `end` is exclusive: it points just past the last character.
+pub struct Span {
+    pub start: Loc,
+    pub end: Loc,
+    /// Byte range of the token in the source. Empty unless set by the tokenizer
+    /// or [`Span::with_bytes`].
+    pub bytes: Range<usize>,
+}
+
+impl Span {
+    pub fn new(start: Loc, end: Loc) -> Self {
+        Self {
+            start,
+            end,
+            bytes: 0..0,
+        }
+    }
+
+    pub fn with_bytes(mut self, bytes: Range<usize>) -> Self {
+        self.bytes = bytes;
+        self
+    }
+
+    /// A span covering `width` characters on a single line.
+    pub fn on_line(line: usize, col: usize, width: usize) -> Self {
+        Self::new(Loc::new(line, col), Loc::new(line, col + width))
+    }
+
+    /// Renders the span's first line from `source` with carets under the span, rustc-style:
+    ///
+    /// ```text
+    ///   |
+    /// 2 | port 8080
+    ///   |      ^^^^
+    /// ```
+    ///
+    /// `tab_width` must match the [`TokenizerOptions::tab_width`] the span was
+    /// lexed with. Tabs before the span are kept in the padding, so the carets
+    /// line up however the terminal renders them.
+    pub fn snippet(&self, source: &str, tab_width: usize) -> String {
+        let (line, col) = (self.start.line(), self.start.col());
+        let text = source
+            .lines()
+            .nth(line.saturating_sub(1))
+            .unwrap_or_default();
+        let end = if self.end.line() == line {
+            self.end.col()
+        } else {
+            usize::MAX
+        };
+
+        let mut indent = String::new();
+        let mut carets = 0usize;
+        let mut visual = 1;
+        for c in text.chars() {
+            if visual >= end {
+                break;
+            }
+            if visual < col {
+                indent.push(if c == '\t' { '\t' } else { ' ' });
+            } else {
+                carets += 1;
+            }
+            visual += if c == '\t' { tab_width } else { 1 };
+        }
+        let gutter = " ".repeat(line.to_string().len());
+
+        format!(
+            "{gutter} |\n{line} | {text}\n{gutter} | {indent}{}",
+            "^".repeat(carets.max(1))
+        )
+    }
+}
+
+impl Display for Span {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        if self.start.line() == self.end.line() {
+            write!(f, "{}-{}", self.start, self.end.col())
+        } else {
+            write!(f, "{}-{}", self.start, self.end)
+        }
     }
 }
 
 #[derive(Debug, Clone)]
-pub struct Token(pub TokenType, pub Loc);
+pub struct Token<'a>(pub TokenType<'a>, pub Span);
+
+impl Token<'_> {
+    /// Start location of the token.
+    pub fn loc(&self) -> &Loc {
+        &self.1.start
+    }
 
-impl Display for TokenType {
+    /// Copies any borrowed text so the token no longer borrows the source.
+    pub fn into_owned(self) -> Token<'static> {
+        let token_type = match self.0 {
+            TokenType::Ident(val) => TokenType::Ident(Cow::Owned(val.into_owned())),
+            TokenType::Keyword(val) => TokenType::Keyword(Cow::Owned(val.into_owned())),
+            TokenType::String(val) => TokenType::String(Cow::Owned(val.into_owned())),
+            TokenType::Int(val) => TokenType::Int(Cow::Owned(val.into_owned())),
+            TokenType::Float(val) => TokenType::Float(Cow::Owned(val.into_owned())),
+            TokenType::Comment(val) => TokenType::Comment(Cow::Owned(val.into_owned())),
+            TokenType::Directive(val) => TokenType::Directive(Cow::Owned(val.into_owned())),
+            TokenType::Bool(val) => TokenType::Bool(val),
+            TokenType::Char(val) => TokenType::Char(val),
+            #[cfg(feature = "base64")]
+            TokenType::Bytes(val) => TokenType::Bytes(val),
+            TokenType::Null => TokenType::Null,
+            TokenType::Equals => TokenType::Equals,
+            TokenType::Colon => TokenType::Colon,
+            TokenType::LeftBrace => TokenType::LeftBrace,
+            TokenType::RightBrace => TokenType::RightBrace,
+            TokenType::LeftBracket => TokenType::LeftBracket,
+            TokenType::RightBracket => TokenType::RightBracket,
+            TokenType::Comma => TokenType::Comma,
+            TokenType::Newline => TokenType::Newline,
+            TokenType::DocumentEnd => TokenType::DocumentEnd,
+        };
+        Token(token_type, self.1)
+    }
+}
+
+impl Display for TokenType<'_> {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
         match self {
             TokenType::Equals => write!(f, "="),
+            TokenType::Colon => write!(f, ":"),
             TokenType::LeftBrace => write!(f, "{{"),
             TokenType::RightBrace => write!(f, "}}"),
-            TokenType::Newline => write!(f, "\n"),
+            TokenType::Newline => writeln!(f),
             TokenType::LeftBracket => write!(f, "["),
             TokenType::RightBracket => write!(f, "]"),
             TokenType::Comma => write!(f, ","),
@@ -48,173 +204,853 @@ impl Display for TokenType {
             | TokenType::String(val) => {
                 write!(f, "{:?}", val)
             }
+            TokenType::Keyword(val) => write!(f, "{}", val),
             TokenType::Bool(val) => write!(f, "{:?}", val),
+            TokenType::Char(val) => write!(f, "{:?}", val),
+            #[cfg(feature = "base64")]
+            TokenType::Bytes(val) => {
+                use base64::Engine;
+                let encoded = base64::engine::general_purpose::STANDARD.encode(val);
+                write!(f, "b64{:?}", encoded)
+            }
+            TokenType::Null => write!(f, "null"),
+            TokenType::Comment(val) => write!(f, "# {}", val),
+            TokenType::Directive(val) => write!(f, "@{}", val),
+            TokenType::DocumentEnd => write!(f, "---"),
         }
     }
 }
 
-pub struct Tokenizer {
-    content: String,
-    tokens: Vec<Token>,
-    index: usize,
+/// A suspicious but valid construct found while lexing.
+#[derive(Debug, Clone)]
+pub struct Warning {
+    pub message: String,
+    pub span: Span,
+}
+
+impl Display for Warning {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{} at {}", self.message, self.span)
+    }
+}
+
+/// Default limit on the size of the whole input, in bytes.
+pub const DEFAULT_MAX_INPUT_BYTES: usize = 256 * 1024 * 1024;
+/// Default limit on the length of a single string literal, in bytes.
+pub const DEFAULT_MAX_STRING_LEN: usize = 16 * 1024 * 1024;
+
+/// Limits for lexing untrusted input, and the identifiers a DSL treats specially.
+#[derive(Debug, Clone)]
+pub struct TokenizerOptions {
+    pub max_input_bytes: usize,
+    pub max_string_len: usize,
+    /// Identifiers lexed as [`TokenType::Keyword`] instead of [`TokenType::Ident`].
+    /// `true`, `false`, `null`, `inf` and `nan` keep their meaning regardless.
+    pub allowed_keywords: HashSet<String>,
+    /// Identifiers that are an error wherever they appear.
+    pub reserved_words: HashSet<String>,
+    /// Columns a tab advances by in reported locations. The default of 1 counts
+    /// characters; set it to the editor's tab width for visual columns.
+    pub tab_width: usize,
+}
+
+impl TokenizerOptions {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn max_input_bytes(mut self, limit: usize) -> Self {
+        self.max_input_bytes = limit;
+        self
+    }
+
+    pub fn max_string_len(mut self, limit: usize) -> Self {
+        self.max_string_len = limit;
+        self
+    }
+
+    pub fn tab_width(mut self, width: usize) -> Self {
+        self.tab_width = width;
+        self
+    }
+
+    pub fn allowed_keywords<I: IntoIterator<Item = S>, S: Into<String>>(
+        mut self,
+        words: I,
+    ) -> Self {
+        self.allowed_keywords = words.into_iter().map(Into::into).collect();
+        self
+    }
+
+    pub fn reserved_words<I: IntoIterator<Item = S>, S: Into<String>>(mut self, words: I) -> Self {
+        self.reserved_words = words.into_iter().map(Into::into).collect();
+        self
+    }
 }
 
-impl Tokenizer {
-    pub fn new(content: impl Into<String>) -> Self {
-        let content = Into::into(content);
+impl Default for TokenizerOptions {
+    fn default() -> Self {
         Self {
-            content,
+            max_input_bytes: DEFAULT_MAX_INPUT_BYTES,
+            max_string_len: DEFAULT_MAX_STRING_LEN,
+            allowed_keywords: HashSet::new(),
+            reserved_words: HashSet::new(),
+            tab_width: 1,
+        }
+    }
+}
+
+/// Lexes a borrowed source. Tokens borrow from it rather than copying their text.
+pub struct Tokenizer<'a> {
+    content: &'a str,
+    options: TokenizerOptions,
+    tokens: Vec<Token<'a>>,
+    warnings: Vec<Warning>,
+    assignment: Option<Span>,
+    index: usize,
+    line: usize,
+    col: usize,
+    /// Braces and brackets currently open.
+    depth: usize,
+    failed: bool,
+}
+
+impl<'a> Tokenizer<'a> {
+    /// Creates a tokenizer over `content`, skipping a leading UTF-8 byte order mark.
+    pub fn new(content: &'a str) -> Self {
+        Self::with_options(content, TokenizerOptions::default())
+    }
+
+    pub fn with_options(content: &'a str, options: TokenizerOptions) -> Self {
+        let mut tokenizer = Self {
+            content: "",
+            options,
             tokens: Vec::new(),
+            warnings: Vec::new(),
+            assignment: None,
             index: 0,
+            line: 1,
+            col: 1,
+            depth: 0,
+            failed: false,
+        };
+        tokenizer.reset(content);
+        tokenizer
+    }
+
+    /// Starts over on new `content`, keeping the token buffer's capacity.
+    pub fn reset(&mut self, content: &'a str) {
+        self.content = content.strip_prefix('\u{FEFF}').unwrap_or(content);
+        self.tokens.clear();
+        self.warnings.clear();
+        self.assignment = None;
+        self.index = 0;
+        self.line = 1;
+        self.col = 1;
+        self.depth = 0;
+        self.failed = false;
+    }
+
+    /// Byte offset into the source of the next character to lex. After an error it
+    /// points at or inside the token that failed.
+    pub fn position(&self) -> usize {
+        self.index
+    }
+
+    /// Line and column of [`Tokenizer::position`].
+    pub fn loc(&self) -> Loc {
+        Loc::new(self.line, self.col)
+    }
+
+    /// Tokens collected by [`Tokenizer::tokenize`]. Empty once it returns them, but
+    /// after an error it still holds every token lexed before the failure.
+    pub fn tokens_ref(&self) -> &[Token<'a>] {
+        &self.tokens
+    }
+
+    /// Warnings collected so far, such as trailing whitespace or empty assignments.
+    pub fn warnings(&self) -> &[Warning] {
+        &self.warnings
+    }
+
+    fn warn(&mut self, message: &str, span: Span) {
+        self.warnings.push(Warning {
+            message: message.to_string(),
+            span,
+        });
+    }
+
+    /// Consumes a run of non-newline whitespace, warning if it trails the line
+    /// or contains a tab past the indentation.
+    fn skip_whitespace(&mut self) -> Result<()> {
+        let start = self.col;
+        let mut has_tab = false;
+        while let Some(c) = self.peek(0) {
+            if !c.is_whitespace() || c == '\n' || c == '\r' {
+                break;
+            }
+            has_tab |= c == '\t';
+            self.consume()?;
+            self.col += self.char_width(c);
+        }
+
+        let span = Span::new(Loc::new(self.line, start), Loc::new(self.line, self.col));
+        if matches!(self.peek(0), None | Some('\n' | '\r')) {
+            self.warn("Trailing whitespace", span);
+        } else if has_tab && start > 1 {
+            self.warn("Tab used for alignment", span);
+        }
+        Ok(())
+    }
+
+    /// Columns `c` takes up, see [`TokenizerOptions::tab_width`].
+    fn char_width(&self, c: char) -> usize {
+        if c == '\t' {
+            self.options.tab_width
+        } else {
+            1
+        }
+    }
+
+    fn check_empty_assignment(&mut self) {
+        if let Some(span) = self.assignment.take() {
+            self.warn("Empty assignment", span);
         }
     }
 
     fn peek(&self, offset: usize) -> Option<char> {
-        self.content.chars().nth(self.index + offset)
+        self.content[self.index..].chars().nth(offset)
     }
 
     fn consume(&mut self) -> Result<char> {
         let c = self
-            .content
-            .chars()
-            .nth(self.index)
-            .ok_or(Error::new(ErrorKind::UnexpectedEof, "End of content!"));
-        self.index += 1;
-        c
+            .peek(0)
+            .ok_or_else(|| Error::new(ErrorKind::UnexpectedEof, "End of content!"))?;
+        self.index += c.len_utf8();
+        Ok(c)
+    }
+
+    fn parse_sign(&mut self, line: usize, col: usize) -> Result<Option<char>> {
+        let Some(sign @ ('+' | '-')) = self.peek(0) else {
+            return Ok(None);
+        };
+        self.consume()?;
+
+        match self.peek(0) {
+            Some(c) if c.is_ascii_digit() => Ok(Some(sign)),
+            Some('i') if self.at_inf() => Ok(Some(sign)),
+            Some(c @ ('+' | '-')) => Err(error_at(
+                ErrorKind::InvalidData,
+                format!("Malformed sign `{}{}` at {}:{}", sign, c, line, col + 1),
+                line,
+                col + 1,
+            )),
+            _ => Err(error_at(
+                ErrorKind::InvalidData,
+                format!("Expected a digit after `{}` at {}:{}", sign, line, col),
+                line,
+                col,
+            )),
+        }
     }
 
-    fn parse_number(&mut self, line: usize, col: usize) -> Result<(Token, usize)> {
-        let mut buf = String::new();
-        buf.push(self.consume()?);
+    fn at_inf(&self) -> bool {
+        self.peek(0) == Some('i')
+            && self.peek(1) == Some('n')
+            && self.peek(2) == Some('f')
+            && !self.peek(3).is_some_and(|c| c.is_alphabetic() || c == '_')
+    }
+
+    fn parse_number(&mut self, line: usize, col: usize) -> Result<(Token<'a>, usize)> {
+        let mut start = self.index;
+        let sign = self.parse_sign(line, col)?;
+        if sign == Some('+') {
+            start = self.index;
+        }
+
+        if self.at_inf() {
+            for _ in 0..3 {
+                self.consume()?;
+            }
+            let width = 4;
+            let buf = &self.content[start..self.index];
+            return Ok((
+                Token(
+                    TokenType::Float(buf.into()),
+                    Span::on_line(line, col, width),
+                ),
+                width,
+            ));
+        }
+        self.consume()?;
         let mut is_float = false;
-        let mut col_delta = 0usize;
+        let mut col_delta = usize::from(sign.is_some());
 
-        while self.peek(0).is_some_and(|c| c.is_digit(10) || c == '.') {
+        while self.peek(0).is_some_and(|c| c.is_ascii_digit() || c == '.') {
             let c = self.peek(0).unwrap();
             if c == '.' {
                 if is_float {
-                    return Err(Error::new(
+                    return Err(error_at(
                         ErrorKind::InvalidData,
-                        format!("Double period `.` at {}:{}", line, col),
+                        format!("Double period `.` at {}:{}", line, col + col_delta + 1),
+                        line,
+                        col + col_delta + 1,
                     ));
                 }
                 is_float = true;
             }
-            buf.push(c);
             self.consume()?;
             col_delta += 1;
         }
 
+        if matches!(self.peek(0), Some('e' | 'E')) {
+            self.consume()?;
+            col_delta += 1;
+            if matches!(self.peek(0), Some('+' | '-')) {
+                self.consume()?;
+                col_delta += 1;
+            }
+            if !self.peek(0).is_some_and(|c| c.is_ascii_digit()) {
+                return Err(error_at(
+                    ErrorKind::InvalidData,
+                    format!(
+                        "Expected a digit in the exponent at {}:{}",
+                        line,
+                        col + col_delta + 1
+                    ),
+                    line,
+                    col + col_delta + 1,
+                ));
+            }
+            while self.peek(0).is_some_and(|c| c.is_ascii_digit()) {
+                self.consume()?;
+                col_delta += 1;
+            }
+            is_float = true;
+        }
+
+        if !is_float
+            && self.peek(0) == Some('u')
+            && !self
+                .peek(1)
+                .is_some_and(|c| c.is_alphanumeric() || c == '_')
+        {
+            self.consume()?;
+            col_delta += 1;
+        }
+
+        let width = col_delta + 1;
+        let span = Span::on_line(line, col, width);
+        let buf = Cow::Borrowed(&self.content[start..self.index]);
         Ok((
             if is_float {
-                Token(TokenType::Float(buf), Loc(col, line))
+                Token(TokenType::Float(buf), span)
             } else {
-                Token(TokenType::Int(buf), Loc(col, line))
+                Token(TokenType::Int(buf), span)
             },
-            col_delta + 1,
+            width,
+        ))
+    }
+
+    /// Consumes a `\n` or `\r\n` line ending if there is one.
+    fn consume_line_ending(&mut self) -> Result<bool> {
+        match (self.peek(0), self.peek(1)) {
+            (Some('\n'), _) => self.consume()?,
+            (Some('\r'), Some('\n')) => {
+                self.consume()?;
+                self.consume()?
+            }
+            _ => return Ok(false),
+        };
+        Ok(true)
+    }
+
+    /// Lexes a `"""` string, which may span lines. A line break right after the
+    /// opening quotes is dropped; everything else, indentation included, is kept
+    /// verbatim, with `\r\n` normalized to `\n`.
+    fn parse_triple_string(&mut self, line: usize, col: usize) -> Result<Token<'a>> {
+        for _ in 0..3 {
+            self.consume()?;
+        }
+        let (mut end_line, mut end_col) = (line, col + 3);
+        if self.consume_line_ending()? {
+            end_line += 1;
+            end_col = 1;
+        }
+        let start = self.index;
+        let end;
+
+        loop {
+            if self.consume_line_ending()? {
+                end_line += 1;
+                end_col = 1;
+                continue;
+            }
+
+            match (self.peek(0), self.peek(1), self.peek(2)) {
+                (Some('"'), Some('"'), Some('"')) => {
+                    end = self.index;
+                    for _ in 0..3 {
+                        self.consume()?;
+                    }
+                    end_col += 3;
+                    break;
+                }
+                (Some('\r'), _, _) => {
+                    return Err(error_at(
+                        ErrorKind::InvalidInput,
+                        format!(
+                            "Carriage return without a newline at {}:{}",
+                            end_line, end_col
+                        ),
+                        end_line,
+                        end_col,
+                    ));
+                }
+                (Some(c), _, _) => {
+                    self.consume()?;
+                    end_col += self.char_width(c);
+                    self.check_string_len(start, line, col)?;
+                }
+                (None, _, _) => {
+                    return Err(error_at(
+                        ErrorKind::UnexpectedEof,
+                        format!("Unterminated `\"\"\"` string at {}:{}", line, col),
+                        line,
+                        col,
+                    ));
+                }
+            }
+        }
+
+        let buf = &self.content[start..end];
+        let buf = if buf.contains('\r') {
+            Cow::Owned(buf.replace("\r\n", "\n"))
+        } else {
+            Cow::Borrowed(buf)
+        };
+        Ok(Token(
+            TokenType::String(buf),
+            Span::new(Loc::new(line, col), Loc::new(end_line, end_col)),
         ))
     }
 
-    fn parse_string(&mut self, line: usize, col: usize) -> Result<(Token, usize)> {
+    /// Lexes a `"..."` string. The escapes `\"`, `\\`, `\n`, `\r`, `\t` and `\0` are
+    /// decoded; a backslash before anything else is kept as is. The text is only
+    /// copied when it contains an escape.
+    ///
+    /// Files written before these escapes were decoded read differently now:
+    /// `"C:\new"` holds a newline where it used to hold a backslash and `n`. Such
+    /// strings need `\\` for the backslash, or a `"""` string, which is read raw.
+    fn parse_string(&mut self, line: usize, col: usize) -> Result<(Token<'a>, usize)> {
         self.consume()?;
-        let mut buf = String::new();
+        let start = self.index;
         let mut col_delta = 0usize;
+        let mut decoded: Option<String> = None;
+        let mut plain_start = start;
 
-        while self.peek(0).is_some_and(|c| c != '"') {
-            if self.peek(0).unwrap() == '\n' {
-                return Err(Error::new(
+        while let Some(c) = self.peek(0).filter(|&c| c != '"') {
+            if matches!(c, '\n' | '\r') {
+                return Err(error_at(
                     ErrorKind::InvalidInput,
                     format!("Un-allowed newline at {}:{}", line, col),
+                    line,
+                    col,
                 ));
             }
-            buf.push(self.consume()?);
-            col_delta += 1;
+            let escape_start = self.index;
+            self.consume()?;
+            col_delta += self.char_width(c);
+            if c == '\\' {
+                if let Some(escaped) = self.peek(0).and_then(|c| unescape(c, '"')) {
+                    self.consume()?;
+                    col_delta += 1;
+                    let out = decoded.get_or_insert_with(String::new);
+                    out.push_str(&self.content[plain_start..escape_start]);
+                    out.push(escaped);
+                    plain_start = self.index;
+                }
+            }
+            self.check_string_len(start, line, col)?;
+        }
+        if self.peek(0).is_none() {
+            return Err(error_at(
+                ErrorKind::UnexpectedEof,
+                format!("Unterminated string at {}:{}", line, col),
+                line,
+                col,
+            ));
+        }
+        let buf = match decoded {
+            Some(mut out) => {
+                out.push_str(&self.content[plain_start..self.index]);
+                Cow::Owned(out)
+            }
+            None => Cow::Borrowed(&self.content[start..self.index]),
+        };
+        self.consume()?;
+
+        let width = col_delta + 2;
+        Ok((
+            Token(TokenType::String(buf), Span::on_line(line, col, width)),
+            width,
+        ))
+    }
+
+    /// Lexes a `'x'` literal, which must hold exactly one character after
+    /// escapes, using the string escapes with `\'` in place of `\"`.
+    fn parse_char(&mut self, line: usize, col: usize) -> Result<(Token<'a>, usize)> {
+        self.consume()?;
+        let start = self.index;
+        let mut text = String::new();
+        while let Some(c) = self
+            .peek(0)
+            .filter(|&c| c != '\'' && c != '\n' && c != '\r')
+        {
+            self.consume()?;
+            match self
+                .peek(0)
+                .filter(|_| c == '\\')
+                .and_then(|c| unescape(c, '\''))
+            {
+                Some(escaped) => {
+                    self.consume()?;
+                    text.push(escaped);
+                }
+                None => text.push(c),
+            }
+        }
+        if self.peek(0) != Some('\'') {
+            return Err(error_at(
+                ErrorKind::InvalidInput,
+                format!("Unterminated character literal at {}:{}", line, col),
+                line,
+                col,
+            ));
+        }
+        let width = self.content[start..self.index]
+            .chars()
+            .map(|c| self.char_width(c))
+            .sum::<usize>()
+            + 2;
+        self.consume()?;
+
+        let mut chars = text.chars();
+        let (Some(c), None) = (chars.next(), chars.next()) else {
+            return Err(error_at(
+                ErrorKind::InvalidInput,
+                format!(
+                    "Character literal must hold exactly one character, got {:?} at {}:{}",
+                    text, line, col
+                ),
+                line,
+                col,
+            ));
+        };
+        Ok((
+            Token(TokenType::Char(c), Span::on_line(line, col, width)),
+            width,
+        ))
+    }
+
+    /// Lexes a `b64"..."` literal, decoding its contents.
+    #[cfg(feature = "base64")]
+    fn parse_bytes(&mut self, line: usize, col: usize) -> Result<(Token<'a>, usize)> {
+        use base64::Engine;
+
+        for _ in 0..4 {
+            self.consume()?;
+        }
+        let start = self.index;
+        while self
+            .peek(0)
+            .is_some_and(|c| c != '"' && c != '\n' && c != '\r')
+        {
+            self.consume()?;
+        }
+        if self.peek(0) != Some('"') {
+            return Err(error_at(
+                ErrorKind::InvalidInput,
+                format!("Unterminated `b64\"` literal at {}:{}", line, col),
+                line,
+                col,
+            ));
         }
+        let text = &self.content[start..self.index];
         self.consume()?;
 
+        let bytes = base64::engine::general_purpose::STANDARD
+            .decode(text)
+            .map_err(|err| {
+                error_at(
+                    ErrorKind::InvalidData,
+                    format!("Invalid base64 at {}:{}: {}", line, col, err),
+                    line,
+                    col,
+                )
+            })?;
+        let width = text.chars().count() + 5;
         Ok((
-            (Token(TokenType::String(buf), Loc(col, line))),
-            col_delta + 1,
+            Token(TokenType::Bytes(bytes), Span::on_line(line, col, width)),
+            width,
         ))
     }
 
-    fn parse_ident(&mut self, line: usize, col: usize) -> Result<(Token, usize)> {
-        let mut buf = String::new();
-        buf.push(self.consume()?);
+    fn parse_ident(&mut self, line: usize, col: usize) -> Result<(Token<'a>, usize)> {
+        let start = self.index;
+        self.consume()?;
         let mut col_delta = 0usize;
 
         while self.peek(0).is_some_and(|c| c.is_alphabetic() || c == '_') {
-            buf.push(self.consume()?);
+            self.consume()?;
             col_delta += 1;
         }
 
-        Ok(match buf.as_str() {
-            "true" => (Token(TokenType::Bool(true), Loc(col, line)), col_delta),
-            "false" => (Token(TokenType::Bool(false), Loc(col, line)), col_delta),
-            _ => (Token(TokenType::Ident(buf), Loc(col, line)), col_delta),
-        })
+        let width = col_delta + 1;
+        let buf = &self.content[start..self.index];
+        if self.options.reserved_words.contains(buf) {
+            return Err(error_at(
+                ErrorKind::InvalidData,
+                format!("Reserved word `{}` at {}:{}", buf, line, col),
+                line,
+                col,
+            ));
+        }
+        let token_type = match buf {
+            "true" => TokenType::Bool(true),
+            "false" => TokenType::Bool(false),
+            "null" => TokenType::Null,
+            "inf" | "nan" => TokenType::Float(buf.into()),
+            _ if self.options.allowed_keywords.contains(buf) => TokenType::Keyword(buf.into()),
+            _ => TokenType::Ident(buf.into()),
+        };
+        Ok((Token(token_type, Span::on_line(line, col, width)), width))
     }
 
-    pub fn tokenize(&mut self) -> Result<Vec<Token>> {
-        let mut line = 1usize;
-        let mut col = 1usize;
+    fn check_string_len(&self, start: usize, line: usize, col: usize) -> Result<()> {
+        if self.index - start > self.options.max_string_len {
+            return Err(error_at(
+                ErrorKind::InvalidData,
+                format!(
+                    "String at {}:{} exceeds the maximum length of {} bytes",
+                    line, col, self.options.max_string_len
+                ),
+                line,
+                col,
+            ));
+        }
+        Ok(())
+    }
 
-        while self.peek(0).is_some() {
-            let c = self.peek(0).unwrap();
+    /// Whether a `---` document separator starts here: alone on its line, apart
+    /// from trailing whitespace, and outside any braces or brackets.
+    fn at_document_end(&self) -> bool {
+        self.col == 1
+            && self.depth == 0
+            && self.content[self.index..]
+                .strip_prefix("---")
+                .is_some_and(|rest| rest.lines().next().unwrap_or("").trim().is_empty())
+    }
 
-            if c == '\n' {
-                line += 1;
-                col = 1;
-                self.tokens.push(Token(TokenType::Newline, Loc(col, line)));
-                self.consume()?;
-            } else {
-                if c.is_whitespace() {
-                    self.consume()?;
-                } else if c.is_digit(10) {
-                    let (t, d) = self.parse_number(line, col)?;
-                    self.tokens.push(t);
-                    col += d;
-                } else if c.is_alphabetic() {
-                    let (t, d) = self.parse_ident(line, col)?;
-                    self.tokens.push(t);
-                    col += d;
-                } else if c == '"' {
-                    let (t, d) = self.parse_string(line, col)?;
-                    self.tokens.push(t);
-                    col += d;
-                } else if c == '{' {
-                    self.tokens
-                        .push(Token(TokenType::LeftBrace, Loc(col, line)));
-                    self.consume()?;
-                } else if c == '}' {
-                    self.tokens
-                        .push(Token(TokenType::RightBrace, Loc(col, line)));
-                    self.consume()?;
-                } else if c == '=' {
-                    self.tokens.push(Token(TokenType::Equals, Loc(col, line)));
-                    self.consume()?;
-                } else if c == '[' {
-                    self.tokens
-                        .push(Token(TokenType::LeftBracket, Loc(col, line)));
-                    self.consume()?;
-                } else if c == ']' {
-                    self.tokens
-                        .push(Token(TokenType::RightBracket, Loc(col, line)));
+    fn next_token(&mut self) -> Result<Option<Token<'a>>> {
+        if self.index == 0 && self.content.len() > self.options.max_input_bytes {
+            return Err(error_at(
+                ErrorKind::InvalidData,
+                format!(
+                    "Input of {} bytes exceeds the maximum of {} bytes",
+                    self.content.len(),
+                    self.options.max_input_bytes
+                ),
+                1,
+                1,
+            ));
+        }
+
+        while let Some(c) = self.peek(0) {
+            let (line, col) = (self.line, self.col);
+            let start = self.index;
+
+            if c == '\r' && self.peek(1) != Some('\n') {
+                return Err(error_at(
+                    ErrorKind::InvalidInput,
+                    format!("Carriage return without a newline at {}:{}", line, col),
+                    line,
+                    col,
+                ));
+            }
+
+            if c == '\n' || c == '\r' {
+                self.check_empty_assignment();
+                if c == '\r' {
                     self.consume()?;
-                } else if c == ',' {
-                    self.tokens.push(Token(TokenType::Comma, Loc(col, line)));
+                }
+                self.line += 1;
+                self.col = 1;
+                self.consume()?;
+                return Ok(Some(Token(
+                    TokenType::Newline,
+                    Span::on_line(self.line, self.col, 0).with_bytes(start..self.index),
+                )));
+            }
+
+            #[cfg(feature = "base64")]
+            if self.content[self.index..].starts_with("b64\"") {
+                let (mut token, width) = self.parse_bytes(line, col)?;
+                self.col += width;
+                self.assignment = None;
+                token.1.bytes = start..self.index;
+                return Ok(Some(token));
+            }
+
+            let token = if c.is_whitespace() {
+                self.skip_whitespace()?;
+                None
+            } else if c == '-' && self.at_document_end() {
+                for _ in 0..3 {
                     self.consume()?;
-                } else {
-                    return Err(Error::new(
+                }
+                self.col += 3;
+                Some(Token(TokenType::DocumentEnd, Span::on_line(line, col, 3)))
+            } else if c.is_ascii_digit() || c == '+' || c == '-' {
+                let (t, d) = self.parse_number(line, col)?;
+                self.col += d;
+                Some(t)
+            } else if c.is_alphabetic() {
+                let (t, d) = self.parse_ident(line, col)?;
+                self.col += d;
+                Some(t)
+            } else if c == '\'' {
+                let (t, d) = self.parse_char(line, col)?;
+                self.col += d;
+                Some(t)
+            } else if c == '@' && self.peek(1).is_some_and(|c| c.is_alphabetic()) {
+                self.consume()?;
+                let (t, d) = self.parse_ident(line, col + 1)?;
+                self.col += d + 1;
+                let TokenType::Ident(name) = t.0 else {
+                    return Err(error_at(
                         ErrorKind::InvalidInput,
-                        format!("Unexpected character {:?} at {}:{}", c, line, col),
+                        format!("Expected a directive name after `@` at {}:{}", line, col),
+                        line,
+                        col,
                     ));
+                };
+                Some(Token(
+                    TokenType::Directive(name),
+                    Span::on_line(line, col, d + 1),
+                ))
+            } else if c == '#' {
+                let start = self.index;
+                while self.peek(0).is_some_and(|c| c != '\n' && c != '\r') {
+                    let c = self.consume()?;
+                    self.col += self.char_width(c);
+                }
+                let text = self.content[start + 1..self.index].trim();
+                Some(Token(
+                    TokenType::Comment(text.into()),
+                    Span::new(Loc::new(line, col), Loc::new(line, self.col)),
+                ))
+            } else if c == '"' {
+                let t = if self.peek(1) == Some('"') && self.peek(2) == Some('"') {
+                    self.parse_triple_string(line, col)?
+                } else {
+                    self.parse_string(line, col)?.0
+                };
+                self.line = t.1.end.line();
+                self.col = t.1.end.col();
+                Some(t)
+            } else {
+                let token_type = match c {
+                    '{' => TokenType::LeftBrace,
+                    '}' => TokenType::RightBrace,
+                    '=' => TokenType::Equals,
+                    ':' => TokenType::Colon,
+                    '[' => TokenType::LeftBracket,
+                    ']' => TokenType::RightBracket,
+                    ',' => TokenType::Comma,
+                    _ => {
+                        return Err(error_at(
+                            ErrorKind::InvalidInput,
+                            format!("Unexpected character {:?} at {}:{}", c, line, col),
+                            line,
+                            col,
+                        ));
+                    }
+                };
+                match token_type {
+                    TokenType::LeftBrace | TokenType::LeftBracket => self.depth += 1,
+                    TokenType::RightBrace | TokenType::RightBracket => {
+                        self.depth = self.depth.saturating_sub(1)
+                    }
+                    _ => {}
                 }
+                self.consume()?;
+                self.col += 1;
+                Some(Token(token_type, Span::on_line(line, col, 1)))
+            };
 
-                col += 1;
+            if let Some(mut token) = token {
+                token.1.bytes = start..self.index;
+                self.assignment = match token.0 {
+                    TokenType::Equals | TokenType::Colon => Some(token.1.clone()),
+                    TokenType::Comment(_) => self.assignment.take(),
+                    _ => None,
+                };
+                return Ok(Some(token));
             }
         }
 
-        Ok(self.tokens.to_vec())
+        self.check_empty_assignment();
+        Ok(None)
+    }
+
+    /// Lexes the rest of the content. The tokens are moved out of the internal
+    /// buffer, leaving [`Tokenizer::tokens_ref`] empty.
+    pub fn tokenize(&mut self) -> Result<Vec<Token<'a>>> {
+        self.tokens.clear();
+        while let Some(token) = self.next() {
+            self.tokens.push(token?);
+        }
+
+        Ok(std::mem::take(&mut self.tokens))
+    }
+}
+
+/// Lexes tokens lazily, one per call. Iteration stops after the first error.
+impl<'a> Iterator for Tokenizer<'a> {
+    type Item = Result<Token<'a>>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.failed {
+            return None;
+        }
+
+        let token = self.next_token();
+        self.failed = token.is_err();
+        token.transpose()
+    }
+}
+
+/// The character a `\` escape stands for, given the character after the `\` and
+/// the literal's delimiter. `None` for unknown escapes, which are kept verbatim.
+fn unescape(c: char, quote: char) -> Option<char> {
+    match c {
+        '\\' => Some('\\'),
+        'n' => Some('\n'),
+        'r' => Some('\r'),
+        't' => Some('\t'),
+        '0' => Some('\0'),
+        c if c == quote => Some(quote),
+        _ => None,
+    }
+}
+
+/// A tokenizer error at `line`:`col`, comparable through [`CoolError::of`].
+fn error_at(kind: ErrorKind, message: String, line: usize, col: usize) -> Error {
+    CoolError::Parse {
+        kind,
+        message,
+        loc: Loc::new(line, col),
     }
+    .into()
 }
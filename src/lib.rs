@@ -1,44 +1,2441 @@
-use std::io::{Result, Write};
+use std::{
+    io::{Error, ErrorKind, Read, Result, Write},
+    path::{Path, PathBuf},
+};
+#[cfg(feature = "quickcheck")]
+mod arbitrary;
+mod convert;
+pub mod diff;
+pub mod error;
+pub mod hash;
+mod interpolate;
 pub mod lexer;
+mod macros;
 pub mod parser;
+pub mod path;
+pub mod schema;
+pub mod ser;
+pub mod stream;
+#[cfg(feature = "toml")]
+mod toml;
+pub mod typed;
+
+#[cfg(feature = "derive")]
+pub use cool_derive::{CoolDeserialize, CoolSerialize};
 
 pub mod prelude {
-    pub use super::parser::{CoolDataList, CoolDataObject, CoolDataType};
-    pub use super::{load_from_file, parse, save_to_file};
+    pub use super::diff::Change;
+    pub use super::error::CoolError;
+    pub use super::hash::HashKey;
+    pub use super::parser::{
+        CoolDataList, CoolDataObject, CoolDataObjectBuilder, CoolDataType, ExpectedType,
+    };
+    pub use super::path::{PathEntry, PathIndex};
+    pub use super::schema::{FieldKind, Schema, ValidationError};
+    pub use super::ser::SerializeOptions;
+    pub use super::typed::{CoolDeserialize, CoolSerialize, FromCool, ToCool};
+    pub use super::{
+        assert_round_trip, load_from_file, load_from_file_interpolated, parse, parse_all,
+        parse_list_stream, parse_multi, parse_stream, parse_value, save_to_file,
+        save_to_file_sorted, save_to_writer,
+    };
+    #[cfg(feature = "derive")]
+    pub use super::{CoolDeserialize, CoolSerialize};
+    pub use crate::cool;
 }
 
+/// Loads and parses a file. Top-level `@include "other.cool"` directives are
+/// resolved relative to the including file's directory and merge the included
+/// fields in; include cycles are an error.
 pub fn load_from_file(file_path: &str) -> Result<parser::CoolDataObject> {
+    load_with_includes(Path::new(file_path), &mut Vec::new())
+}
+
+fn load_with_includes(path: &Path, stack: &mut Vec<PathBuf>) -> Result<parser::CoolDataObject> {
     use std::fs::read_to_string;
-    let content = read_to_string(file_path)?;
-    let mut tokenizer = lexer::Tokenizer::new(content);
+    let canonical = path.canonicalize()?;
+    if stack.contains(&canonical) {
+        let chain: Vec<String> = stack
+            .iter()
+            .chain([&canonical])
+            .map(|path| path.display().to_string())
+            .collect();
+        return Err(Error::new(
+            ErrorKind::InvalidData,
+            format!("Include cycle: {}", chain.join(" -> ")),
+        ));
+    }
+    let content = read_to_string(path)?;
+    let dir = path.parent().unwrap_or(Path::new("")).to_path_buf();
+
+    stack.push(canonical);
+    let mut tokenizer = lexer::Tokenizer::new(content.as_str());
     let tokens = tokenizer.tokenize()?;
+    let object = parser::Parser::new(tokens)
+        .with_source(content.as_str())
+        .with_include_resolver(|include| load_with_includes(&dir.join(include), stack))
+        .parse();
+    stack.pop();
+    object
+}
 
-    let mut parser = parser::Parser::new(tokens);
-    parser.parse()
+/// Like [`load_from_file`], but expands `${VAR}` references in string values from
+/// the environment, see [`parser::CoolDataObject::interpolate_with`].
+pub fn load_from_file_interpolated(file_path: &str) -> Result<parser::CoolDataObject> {
+    let mut object = load_from_file(file_path)?;
+    object.interpolate_env()?;
+    Ok(object)
 }
 
 pub fn save_to_file(file_path: &str, object: &parser::CoolDataObject) -> Result<()> {
-    use std::fs::File;
-    let mut file = File::create(file_path)?;
-    for (key, value) in object.clone().into_iter() {
-        write!(file, "{} = {}\n", key, value)?;
-    }
-    file.flush()?;
+    let file = std::fs::File::create(file_path)?;
+    save_to_writer(std::io::BufWriter::new(file), object)
+}
 
-    Ok(())
+/// Writes the object to `writer` in the same format as [`save_to_file`], streaming
+/// it rather than formatting the whole document first.
+pub fn save_to_writer<W: Write>(mut writer: W, object: &parser::CoolDataObject) -> Result<()> {
+    object.write_to(&mut writer, &ser::SerializeOptions::default())?;
+    writer.flush()
+}
+
+/// Like [`save_to_file`], but writes keys in sorted order at every level for reproducible output.
+pub fn save_to_file_sorted(file_path: &str, object: &parser::CoolDataObject) -> Result<()> {
+    save_to_file(file_path, &object.sorted())
 }
 
 pub fn parse(content: impl Into<String>) -> Result<parser::CoolDataObject> {
-    let mut tokenizer = lexer::Tokenizer::new(content);
+    let content = content.into();
+    let mut tokenizer = lexer::Tokenizer::new(content.as_str());
     let tokens = tokenizer.tokenize()?;
 
-    let mut parser = parser::Parser::new(tokens);
+    let mut parser = parser::Parser::new(tokens).with_source(content.as_str());
     parser.parse()
 }
 
+/// Parses a document whose root isn't necessarily an object, such as `[1, 2, 3]`
+/// or `"hello"`. See [`parser::Parser::parse_document`] for how the root is chosen.
+pub fn parse_value(content: impl Into<String>) -> Result<parser::CoolDataType> {
+    let content = content.into();
+    let mut tokenizer = lexer::Tokenizer::new(content.as_str());
+    let tokens = tokenizer.tokenize()?;
+
+    let mut parser = parser::Parser::new(tokens).with_source(content.as_str());
+    parser.parse_document()
+}
+
+/// Parses several documents separated by lines consisting of just `---`, see
+/// [`parser::Parser::parse_stream`]. Separators inside strings, braces or
+/// brackets don't split the document.
+pub fn parse_stream(content: impl Into<String>) -> Result<Vec<parser::CoolDataObject>> {
+    let content = content.into();
+    let mut tokenizer = lexer::Tokenizer::new(content.as_str());
+    let tokens = tokenizer.tokenize()?;
+
+    let mut parser = parser::Parser::new(tokens).with_source(content.as_str());
+    parser.parse_stream()
+}
+
+/// Same as [`parse_stream`].
+pub fn parse_multi(content: impl Into<String>) -> Result<Vec<parser::CoolDataObject>> {
+    parse_stream(content)
+}
+
+/// Parses a top-level list from `reader` one element at a time, so huge lists can be
+/// processed with bounded memory. Error locations are relative to the element.
+pub fn parse_list_stream<R: Read>(reader: R) -> impl Iterator<Item = Result<parser::CoolDataType>> {
+    stream::ListStream::new(reader)
+}
+
+/// Parses `content` reporting every error rather than just the first, for editor and
+/// linter integrations. The object holds the fields that parsed successfully and is
+/// `None` only when the content couldn't be tokenized at all.
+pub fn parse_all(content: impl Into<String>) -> (Option<parser::CoolDataObject>, Vec<Error>) {
+    let content = content.into();
+    let mut tokenizer = lexer::Tokenizer::new(content.as_str());
+    let tokens = match tokenizer.tokenize() {
+        Ok(tokens) => tokens,
+        Err(err) => return (None, vec![err]),
+    };
+
+    let mut parser = parser::Parser::new(tokens).with_source(content.as_str());
+    let (object, errors) = parser.parse_all();
+    (Some(object), errors)
+}
+
+/// Checks that `input` survives a parse, serialize, re-parse cycle unchanged,
+/// returning an `InvalidData` error describing the divergence otherwise. Useful
+/// to verify extensions keep the format lossless. Documents containing NaN never
+/// pass, as NaN doesn't compare equal to itself.
+pub fn assert_round_trip(input: &str) -> Result<()> {
+    let first = parse(input)?;
+    let serialized = first.to_string();
+    let second = parse(serialized.as_str()).map_err(|err| {
+        Error::new(
+            ErrorKind::InvalidData,
+            format!("Serialized output failed to parse: {}\n{}", err, serialized),
+        )
+    })?;
+
+    if first != second {
+        return Err(Error::new(
+            ErrorKind::InvalidData,
+            format!(
+                "Round trip changed the document.\nParsed: {:?}\nSerialized:\n{}Re-parsed: {:?}",
+                first, serialized, second
+            ),
+        ));
+    }
+    Ok(())
+}
+
 #[cfg(test)]
 mod tests {
     #![allow(unused_imports)]
     use super::prelude::*;
     use std::io::Result;
+    use std::path::PathBuf;
+
+    /// A directory of its own under the system temp dir for one test, removed
+    /// again when dropped, including when the test fails.
+    struct TempDir(PathBuf);
+
+    impl TempDir {
+        fn new(test: &str) -> Self {
+            let dir = std::env::temp_dir().join(format!("cool_{}_{}", test, std::process::id()));
+            let _ = std::fs::remove_dir_all(&dir);
+            std::fs::create_dir_all(&dir).unwrap();
+            Self(dir)
+        }
+
+        fn path(&self, name: &str) -> PathBuf {
+            self.0.join(name)
+        }
+    }
+
+    impl Drop for TempDir {
+        fn drop(&mut self) {
+            let _ = std::fs::remove_dir_all(&self.0);
+        }
+    }
+
+    #[test]
+    fn deeply_nested_input_errors() {
+        let err = parse("a = {".repeat(10_000)).unwrap_err();
+        assert_eq!(err.kind(), std::io::ErrorKind::InvalidData);
+    }
+
+    #[test]
+    fn comparable_parse_errors() {
+        use crate::lexer::Loc;
+        use std::io::ErrorKind;
+
+        let first = parse("x = 1\ny 2\n").unwrap_err();
+        let second = parse("x = 1\ny 2\n").unwrap_err();
+        assert_eq!(CoolError::of(&first), CoolError::of(&second));
+        assert_eq!(
+            CoolError::of(&first),
+            Some(&CoolError::Parse {
+                kind: ErrorKind::InvalidData,
+                message: first.to_string(),
+                loc: Loc::new(2, 3),
+            })
+        );
+        assert_eq!(first.kind(), ErrorKind::InvalidData);
+
+        let other = parse("x = 1\ny 3\n").unwrap_err();
+        assert_ne!(CoolError::of(&first), CoolError::of(&other));
+
+        let tokenizer_errors = [
+            ("s = \"open\n", ErrorKind::InvalidInput, Loc::new(1, 5)),
+            ("n = 1.2.3\n", ErrorKind::InvalidData, Loc::new(1, 8)),
+            ("x = ?\n", ErrorKind::InvalidInput, Loc::new(1, 5)),
+        ];
+        for (content, kind, loc) in tokenizer_errors {
+            let err = parse(content).unwrap_err();
+            assert_eq!(
+                CoolError::of(&err),
+                Some(&CoolError::Parse {
+                    kind,
+                    message: err.to_string(),
+                    loc,
+                })
+            );
+            assert_eq!(
+                CoolError::of(&parse(content).unwrap_err()),
+                CoolError::of(&err)
+            );
+        }
+    }
+
+    #[test]
+    fn custom_max_depth() -> Result<()> {
+        use crate::{lexer::Tokenizer, parser::Parser};
+
+        let content = "a = { b = { c = 1\n}\n}\n";
+        let tokens = Tokenizer::new(content).tokenize()?;
+        assert!(Parser::with_max_depth(tokens.clone(), 1).parse().is_err());
+
+        let object = Parser::with_max_depth(tokens, 2).parse()?;
+        assert_eq!(*object.get_object("a")?.get_object("b")?.get_int("c")?, 1);
+        Ok(())
+    }
+
+    fn int_list(values: &[i64]) -> CoolDataList {
+        CoolDataList(values.iter().map(|&v| CoolDataType::Int(v)).collect())
+    }
+
+    #[test]
+    fn list_swap() -> Result<()> {
+        let mut list = int_list(&[1, 2, 3]);
+        list.swap(0, 2)?;
+        assert_eq!(*list.int_at(0)?, 3);
+        assert_eq!(*list.int_at(2)?, 1);
+        Ok(())
+    }
+
+    #[test]
+    fn list_swap_out_of_bounds() {
+        let mut list = int_list(&[1, 2, 3]);
+        assert!(list.swap(0, 3).is_err());
+        assert_eq!(*list.int_at(0).unwrap(), 1);
+    }
+
+    #[test]
+    fn list_truncate() {
+        let mut list = int_list(&[1, 2, 3]);
+        list.truncate(1);
+        assert_eq!(list.0.len(), 1);
+        assert!(list.at(1).is_err());
+    }
+
+    #[test]
+    fn field_order_survives_load_save() -> Result<()> {
+        let dir = TempDir::new("field_order_survives_load_save");
+        let input = dir.path("in.cool");
+        let output = dir.path("out.cool");
+        let content = "zeta = 1\nalpha = \"a\"\nmid = { y = 2\nx = 3\n}\nbeta = true\n";
+        std::fs::write(&input, content)?;
+
+        let object = load_from_file(input.to_str().unwrap())?;
+        let keys: Vec<_> = object.iter().map(|(key, _)| key.as_str()).collect();
+        assert_eq!(keys, ["zeta", "alpha", "mid", "beta"]);
+
+        save_to_file(output.to_str().unwrap(), &object)?;
+        let saved = std::fs::read_to_string(&output)?;
+        assert_eq!(
+            saved,
+            "zeta = 1\nalpha = \"a\"\nmid = {\ny = 2\nx = 3\n}\nbeta = true\n"
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn sorted_save_is_independent_of_insertion_order() -> Result<()> {
+        let mut inner_a = CoolDataObject::new();
+        inner_a.add_field("y".into(), CoolDataType::Int(1));
+        inner_a.add_field("x".into(), CoolDataType::Int(2));
+        let mut a = CoolDataObject::new();
+        a.add_field("b".into(), CoolDataType::Bool(true));
+        a.add_field("a".into(), CoolDataType::Object(inner_a));
+
+        let mut inner_b = CoolDataObject::new();
+        inner_b.add_field("x".into(), CoolDataType::Int(2));
+        inner_b.add_field("y".into(), CoolDataType::Int(1));
+        let mut b = CoolDataObject::new();
+        b.add_field("a".into(), CoolDataType::Object(inner_b));
+        b.add_field("b".into(), CoolDataType::Bool(true));
+
+        let dir = TempDir::new("sorted_save_is_independent_of_insertion_order");
+        let path_a = dir.path("a.cool");
+        let path_b = dir.path("b.cool");
+        save_to_file_sorted(path_a.to_str().unwrap(), &a)?;
+        save_to_file_sorted(path_b.to_str().unwrap(), &b)?;
+
+        let saved = std::fs::read(&path_a)?;
+        assert_eq!(saved, std::fs::read(&path_b)?);
+        assert_eq!(saved, b"a = {\nx = 2\ny = 1\n}\nb = true\n");
+        Ok(())
+    }
+
+    #[test]
+    fn sorting_keeps_comments() -> Result<()> {
+        use crate::{lexer::Tokenizer, parser::Parser};
+
+        let content = "# The port.\nport = 80 # http\nhost = { name = \"x\" # short\n}\n";
+        let tokens = Tokenizer::new(content).tokenize()?;
+        let mut object = Parser::new(tokens)
+            .preserve_comments(true)
+            .preserve_docs(true)
+            .parse()?;
+        object.set_case_insensitive(true);
+
+        let sorted = object.sorted();
+        assert_eq!(
+            sorted.to_string(),
+            "host = {\nname = \"x\" # short\n}\n# The port.\nport = 80 # http\n"
+        );
+        assert_eq!(sorted.get_int("PORT")?, &80);
+
+        let dir = TempDir::new("sorting_keeps_comments");
+        let path = dir.path("sorted.cool");
+        save_to_file_sorted(path.to_str().unwrap(), &object)?;
+        assert_eq!(std::fs::read_to_string(&path)?, sorted.to_string());
+        Ok(())
+    }
+
+    #[test]
+    fn path_index_lookups() -> Result<()> {
+        let object = parse("server = { host = \"localhost\"\nport = 8080\ntls = { enabled = true\n}\n}\nname = \"app\"\n")?;
+        let index = object.path_index();
+
+        assert!(
+            matches!(index.get("server.host")?, CoolDataType::String(val) if val == "localhost")
+        );
+        assert!(matches!(index.get("server.port")?, CoolDataType::Int(8080)));
+        assert!(matches!(
+            index.get("server.tls.enabled")?,
+            CoolDataType::Bool(true)
+        ));
+        assert!(matches!(index.get("name")?, CoolDataType::String(_)));
+        assert!(matches!(index.get("server")?, CoolDataType::Object(_)));
+        assert!(index.get("server.missing").is_err());
+
+        assert!(matches!(
+            object.get_path("server.tls.enabled")?,
+            CoolDataType::Bool(true)
+        ));
+        Ok(())
+    }
+
+    #[test]
+    fn entry_increments_counter() -> Result<()> {
+        let mut object = parse("seen = 41\n")?;
+        for key in ["seen", "fresh"] {
+            if let CoolDataType::Int(count) =
+                object.entry(key.into()).or_insert(CoolDataType::Int(0))
+            {
+                *count += 1;
+            }
+        }
+        assert_eq!(*object.get_int("seen")?, 42);
+        assert_eq!(*object.get_int("fresh")?, 1);
+
+        let value = object
+            .entry("lazy".into())
+            .or_insert_with(|| CoolDataType::Bool(true));
+        assert!(matches!(value, CoolDataType::Bool(true)));
+        Ok(())
+    }
+
+    #[test]
+    fn structural_mode_free_form_layout() -> Result<()> {
+        use crate::{lexer::Tokenizer, parser::Parser};
+
+        let content = "name\n=\n\"app\" port =\n8080 tags = [\n\"a\",\n\"b\"\n] db = { host\n= \"localhost\" }";
+        let tokens = Tokenizer::new(content).tokenize()?;
+        assert!(Parser::new(tokens.clone()).parse().is_err());
+
+        let object = Parser::new(tokens).structural_mode(true).parse()?;
+        assert_eq!(object.get_string("name")?, "app");
+        assert_eq!(*object.get_int("port")?, 8080);
+        assert_eq!(object.get_list("tags")?.string_at(1)?, "b");
+        assert_eq!(object.get_object("db")?.get_string("host")?, "localhost");
+        Ok(())
+    }
+
+    #[test]
+    fn structural_mode_parse_all_and_flags() -> Result<()> {
+        use crate::{lexer::Tokenizer, parser::Parser};
+
+        let tokens = Tokenizer::new("a = 1 b 2 c = { x = 1 y 3 } d = true").tokenize()?;
+        let (object, errors) = Parser::new(tokens).structural_mode(true).parse_all();
+        assert_eq!(errors.len(), 2);
+        assert_eq!(*object.get_int("a")?, 1);
+        assert!(*object.get_bool("d")?);
+        assert!(object.get_field("b").is_err());
+        assert!(object.get_field("c").is_err());
+
+        let tokens = Tokenizer::new("verbose name = \"x\" server = { tls } debug").tokenize()?;
+        let object = Parser::new(tokens)
+            .structural_mode(true)
+            .flag_fields(true)
+            .parse()?;
+        assert!(*object.get_bool("verbose")?);
+        assert_eq!(object.get_string("name")?, "x");
+        assert!(*object.get_object("server")?.get_bool("tls")?);
+        assert!(*object.get_bool("debug")?);
+        Ok(())
+    }
+
+    #[test]
+    fn list_push_helpers() -> Result<()> {
+        let mut inner = CoolDataObject::new();
+        inner.add_field("x".into(), CoolDataType::Int(3));
+
+        let mut list = CoolDataList::new();
+        list.push_int(1).push_string("two").push_object(inner);
+        assert_eq!(list.to_string(), "[\n1,\n\"two\",\n{\nx = 3\n},\n]");
+
+        let mut object = CoolDataObject::new();
+        object.add_field("items".into(), CoolDataType::List(list));
+        let reparsed = parse(object.to_string())?;
+        let items = reparsed.get_list("items")?;
+        assert_eq!(*items.int_at(0)?, 1);
+        assert_eq!(items.string_at(1)?, "two");
+        assert_eq!(*items.object_at(2)?.get_int("x")?, 3);
+        Ok(())
+    }
+
+    #[test]
+    fn str_pairs_with_hints() -> Result<()> {
+        use std::collections::HashMap;
+
+        let pairs = [
+            ("zip", "01234"),
+            ("count", "3"),
+            ("ratio", "0.5"),
+            ("debug", "true"),
+            ("name", "app"),
+        ];
+        let hints = HashMap::from([
+            ("zip", ExpectedType::String),
+            ("ratio", ExpectedType::Float),
+        ]);
+        let object = CoolDataObject::from_str_pairs_with_hints(&pairs, &hints)?;
+        assert_eq!(object.get_string("zip")?, "01234");
+        assert_eq!(*object.get_int("count")?, 3);
+        assert_eq!(*object.get_float("ratio")?, 0.5);
+        assert!(*object.get_bool("debug")?);
+        assert_eq!(object.get_string("name")?, "app");
+
+        let hints = HashMap::from([("count", ExpectedType::Int)]);
+        let err =
+            CoolDataObject::from_str_pairs_with_hints(&[("count", "many")], &hints).unwrap_err();
+        assert!(err.to_string().contains("\"count\""));
+        Ok(())
+    }
+
+    #[test]
+    fn token_iterator_matches_tokenize() -> Result<()> {
+        use crate::lexer::Tokenizer;
+
+        let content = "name = \"app\"\nlimits = { cpu = 1.5\nmem = 512 }\ntags = [\"a\", true]\n";
+        let tokenized = Tokenizer::new(content).tokenize()?;
+        let iterated = Tokenizer::new(content).collect::<Result<Vec<_>>>()?;
+        assert_eq!(format!("{:?}", tokenized), format!("{:?}", iterated));
+
+        let mut tokens = Tokenizer::new("x = ?");
+        assert_eq!(tokens.by_ref().filter(|token| token.is_err()).count(), 1);
+        assert!(tokens.next().is_none());
+        Ok(())
+    }
+
+    #[test]
+    fn stable_number_strings() {
+        assert_eq!(CoolDataType::Float(-0.0).to_stable_string(), "-0.0");
+        assert_eq!(CoolDataType::Float(0.0).to_stable_string(), "0.0");
+        assert_eq!(
+            CoolDataType::Float(1.2345678).to_stable_string(),
+            "1.2345678"
+        );
+        assert_eq!(CoolDataType::Float(0.1).to_stable_string(), "0.1");
+        assert_eq!(CoolDataType::Float(1e20).to_stable_string(), "1e20");
+        assert_eq!(
+            CoolDataType::Float(f32::NEG_INFINITY).to_stable_string(),
+            "-inf"
+        );
+        assert_eq!(CoolDataType::Int(-7).to_stable_string(), "-7");
+
+        let mut list = CoolDataList::new();
+        list.push_float(2.0).push_string("s");
+        assert_eq!(CoolDataType::List(list).to_stable_string(), "[2.0, \"s\"]");
+    }
+
+    #[test]
+    fn token_spans() -> Result<()> {
+        use crate::{lexer::Tokenizer, parser::Parser};
+
+        let tokens = Tokenizer::new("count = 123 name = \"ab\"").tokenize()?;
+        let spans: Vec<String> = tokens.iter().map(|token| token.1.to_string()).collect();
+        assert_eq!(
+            spans,
+            ["1:1-6", "1:7-8", "1:9-12", "1:13-17", "1:18-19", "1:20-24"]
+        );
+        assert_eq!(tokens[2].loc().0, 9);
+
+        let err = Parser::new(Tokenizer::new("x 1\n").tokenize()?)
+            .parse()
+            .unwrap_err();
+        assert!(err.to_string().ends_with("at 1:3-4"), "{}", err);
+        Ok(())
+    }
+
+    #[test]
+    fn merge_with_resolver() -> Result<()> {
+        let mut base = parse("hits = 3\nname = \"base\"\n")?;
+        let other = parse("hits = 4\nextra = true\nname = \"other\"\n")?;
+        base.merge_with(other, |_, current, incoming| match (current, incoming) {
+            (CoolDataType::Int(a), CoolDataType::Int(b)) => CoolDataType::Int(a + b),
+            _ => incoming.clone(),
+        });
+
+        assert_eq!(*base.get_int("hits")?, 7);
+        assert_eq!(base.get_string("name")?, "other");
+        assert!(*base.get_bool("extra")?);
+        Ok(())
+    }
+
+    #[test]
+    fn errors_quote_source_line() {
+        let err = parse("name = \"app\"\nport 8080\n").unwrap_err();
+        assert_eq!(
+            err.to_string(),
+            "Exptected `=` or `:`, got `\"8080\"` at 2:6-10\n  |\n2 | port 8080\n  |      ^^^^"
+        );
+    }
+
+    #[test]
+    fn shrink_on_finish() -> Result<()> {
+        use crate::{lexer::Tokenizer, parser::Parser};
+
+        let mut content: String = (0..100)
+            .map(|i| format!("field_{} = {}\n", "a".repeat(i), i))
+            .collect();
+        content.push_str("list = [");
+        content.push_str(&"1, ".repeat(100));
+        content.push_str("]\n");
+        let tokens = Tokenizer::new(&content).tokenize()?;
+
+        let grown = Parser::new(tokens.clone()).parse()?;
+        let shrunk = Parser::new(tokens).shrink_on_finish(true).parse()?;
+        assert!(shrunk.capacity() < grown.capacity());
+        assert!(shrunk.get_list("list")?.0.capacity() < grown.get_list("list")?.0.capacity());
+        Ok(())
+    }
+
+    #[test]
+    fn parse_all_reports_every_error() {
+        let content = "a = 1\nb 2\nc = { x = 1\ny 3\n}\nd = true\n";
+        let (object, errors) = parse_all(content);
+        let object = object.unwrap();
+
+        assert_eq!(errors.len(), 2);
+        assert!(errors[0].to_string().contains("at 2:3-4"), "{}", errors[0]);
+        assert!(errors[1].to_string().contains("at 4:3-4"), "{}", errors[1]);
+        assert_eq!(*object.get_int("a").unwrap(), 1);
+        assert!(*object.get_bool("d").unwrap());
+        assert!(object.get_field("c").is_err());
+    }
+
+    #[test]
+    fn null_present_vs_absent() -> Result<()> {
+        let object = parse("db = { password = null\nuser = \"admin\"\n}\n")?;
+
+        assert!(matches!(
+            object.get_path("db.password")?,
+            CoolDataType::Null
+        ));
+        assert!(object.is_present_and_null("db.password"));
+        assert!(!object.is_absent("db.password"));
+
+        assert!(!object.is_present_and_null("db.user"));
+        assert!(!object.is_absent("db.user"));
+
+        assert!(!object.is_present_and_null("db.port"));
+        assert!(object.is_absent("db.port"));
+        Ok(())
+    }
+
+    #[test]
+    fn colon_assignments() -> Result<()> {
+        let object = parse("name: \"app\"\nport = 8080\ndb: { host: \"localhost\"\n}\n")?;
+        assert_eq!(object.get_string("name")?, "app");
+        assert_eq!(*object.get_int("port")?, 8080);
+        assert_eq!(object.get_object("db")?.get_string("host")?, "localhost");
+        assert_eq!(
+            object.to_string(),
+            "name = \"app\"\nport = 8080\ndb = {\nhost = \"localhost\"\n}\n"
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn query_string() -> Result<()> {
+        let object = parse(
+            "q = \"hello world\"\npage = 2\nexact = true\nfilters = { a = 1\n}\nsym = \"a&b=c\"\n",
+        )?;
+        assert_eq!(
+            object.to_query_string(),
+            "q=hello%20world&page=2&exact=true&sym=a%26b%3Dc"
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn quoted_keys() -> Result<()> {
+        let object = parse(
+            "\"display name\" = \"Ada\"\n\"key.with.dots\" = 1\nplain = { \"a b\" = true\n}\n",
+        )?;
+        assert_eq!(object.get_string("display name")?, "Ada");
+        assert_eq!(*object.get_int("key.with.dots")?, 1);
+        assert!(object.get_path("key.with.dots").is_err());
+        assert!(*object.get_object("plain")?.get_bool("a b")?);
+
+        let saved = object.to_string();
+        assert_eq!(
+            saved,
+            "\"display name\" = \"Ada\"\n\"key.with.dots\" = 1\nplain = {\n\"a b\" = true\n}\n"
+        );
+        assert_eq!(*parse(saved)?.get_int("key.with.dots")?, 1);
+
+        let mut reserved = CoolDataObject::new();
+        for key in ["true", "false", "null", "inf", "nan"] {
+            reserved.add_field(key.into(), 1.into());
+        }
+        assert!(reserved.to_string().starts_with("\"true\" = 1\n"));
+        assert!(reserved.to_string().contains("\"inf\" = 1\n\"nan\" = 1\n"));
+        assert_eq!(parse(reserved.to_string())?, reserved);
+        Ok(())
+    }
+
+    #[test]
+    fn merge_tracks_provenance() -> Result<()> {
+        let mut config = CoolDataObject::new();
+        config.merge_tracked(parse("port = 80\nhost = \"localhost\"\n")?, "defaults.cool");
+        config.merge_tracked(parse("port = 8080\n")?, "local.cool");
+
+        assert_eq!(*config.get_int("port")?, 8080);
+        assert_eq!(config.field_source("port"), Some("local.cool"));
+        assert_eq!(config.field_source("host"), Some("defaults.cool"));
+        assert_eq!(config.field_source("missing"), None);
+        Ok(())
+    }
+
+    #[test]
+    fn signed_numbers() -> Result<()> {
+        let object = parse("offset = +10\nzero = -0\nneg = -42\nratio = -2.5\n")?;
+        assert_eq!(*object.get_int("offset")?, 10);
+        assert_eq!(*object.get_int("zero")?, 0);
+        assert_eq!(*object.get_int("neg")?, -42);
+        assert_eq!(*object.get_float("ratio")?, -2.5);
+
+        let err = parse("x = +-5\n").unwrap_err();
+        assert_eq!(err.to_string(), "Malformed sign `+-` at 1:6");
+        let err = parse("x = --5\n").unwrap_err();
+        assert_eq!(err.to_string(), "Malformed sign `--` at 1:6");
+        let err = parse("x = - 5\n").unwrap_err();
+        assert_eq!(err.to_string(), "Expected a digit after `-` at 1:5");
+        Ok(())
+    }
+
+    #[test]
+    fn preserve_case_insensitive() -> Result<()> {
+        use crate::{lexer::Tokenizer, parser::Parser};
+
+        let tokens = Tokenizer::new("MyKey = 1\nNested = { InnerKey = true\n}\n").tokenize()?;
+        let mut object = Parser::new(tokens)
+            .preserve_case_insensitive(true)
+            .parse()?;
+
+        assert_eq!(*object.get_int("mykey")?, 1);
+        assert_eq!(*object.get_int("MYKEY")?, 1);
+        assert!(*object.get_object("nested")?.get_bool("innerkey")?);
+        *object.get_int_mut("mykey")? = 2;
+        assert_eq!(
+            object.to_string(),
+            "MyKey = 2\nNested = {\nInnerKey = true\n}\n"
+        );
+
+        assert!(parse("MyKey = 1\n")?.get_field("mykey").is_err());
+        Ok(())
+    }
+
+    #[test]
+    fn inf_and_nan_floats() -> Result<()> {
+        let content = "hi = inf\nlo = -inf\nup = +inf\nbad = nan\n";
+        let object = parse(content)?;
+        assert_eq!(*object.get_float("hi")?, f32::INFINITY);
+        assert_eq!(*object.get_float("lo")?, f32::NEG_INFINITY);
+        assert_eq!(*object.get_float("up")?, f32::INFINITY);
+        assert!(object.get_float("bad")?.is_nan());
+
+        assert_eq!(
+            object.to_string(),
+            "hi = inf\nlo = -inf\nup = inf\nbad = nan\n"
+        );
+        assert!(parse("x = -info\n").is_err());
+        Ok(())
+    }
+
+    #[test]
+    fn first_and_last_of_type() -> Result<()> {
+        let object = parse("items = [1, \"a\", { id = 1 }, 2.5, { id = 2 }, \"b\"]\n")?;
+        let items = object.get_list("items")?;
+
+        assert_eq!(*items.first_object().unwrap().get_int("id")?, 1);
+        assert_eq!(*items.last_object().unwrap().get_int("id")?, 2);
+        assert_eq!(items.first_string().unwrap(), "a");
+        assert_eq!(items.last_string().unwrap(), "b");
+        assert_eq!(items.first_float(), Some(&2.5));
+        assert!(items.first_list().is_none());
+        Ok(())
+    }
+
+    #[test]
+    fn crlf_line_endings() -> Result<()> {
+        let lf = "name = \"app\"\nlimits = {\n  cpu = 2\n}\nport 8080\n";
+        let crlf = lf.replace('\n', "\r\n");
+
+        let object = parse(&crlf[..crlf.find("port").unwrap()])?;
+        assert_eq!(object.get_string("name")?, "app");
+        assert_eq!(*object.get_object("limits")?.get_int("cpu")?, 2);
+
+        let lf_err = parse(lf).unwrap_err().to_string();
+        let crlf_err = parse(crlf).unwrap_err().to_string();
+        assert!(lf_err.starts_with("Exptected `=` or `:`, got `\"8080\"` at 5:6-10"));
+        assert_eq!(crlf_err.lines().next(), lf_err.lines().next());
+
+        assert!(parse("x = 1\ry = 2\n").is_err());
+        assert!(parse("x = \"a\rb\"\n").is_err());
+        Ok(())
+    }
+
+    #[test]
+    fn round_trips() -> Result<()> {
+        assert_round_trip("")?;
+        assert_round_trip("name = \"app\"\nport = 8080\n")?;
+        assert_round_trip(
+            "outer = { inner = { deep = true\n}\nlist = [1, [2, 3], { x = 4 }]\n}\n",
+        )?;
+        assert_round_trip("whole = 3.0\nneg = -0.0\nhuge = 12345678.5\nsmall = -0.001\n")?;
+        assert_round_trip("empty_obj = {}\nempty_list = []\nnothing = null\n\"odd key\" = inf\n")?;
+        Ok(())
+    }
+
+    #[test]
+    fn round_trip_reports_divergence() {
+        let err = assert_round_trip("x = nan\n").unwrap_err();
+        assert_eq!(err.kind(), std::io::ErrorKind::InvalidData);
+        assert!(err.to_string().contains("Round trip changed the document."));
+    }
+
+    #[test]
+    fn leading_bom_is_skipped() -> Result<()> {
+        use crate::lexer::Tokenizer;
+
+        let tokens = Tokenizer::new("\u{FEFF}x = 1").tokenize()?;
+        assert_eq!(tokens[0].loc().0, 1);
+        assert_eq!(*parse("\u{FEFF}x = 1")?.get_int("x")?, 1);
+        Ok(())
+    }
+
+    #[test]
+    fn explicit_plus_sign() -> Result<()> {
+        let object = parse("a = +5\nb = +2.75\n")?;
+        assert_eq!(object.get_field("a")?, &CoolDataType::Int(5));
+        assert_eq!(object.get_field("b")?, &CoolDataType::Float(2.75));
+
+        assert_eq!(CoolDataType::int("+7")?, CoolDataType::Int(7));
+        assert!(CoolDataType::int("+-7").is_err());
+
+        let err = parse("a = +\n").unwrap_err();
+        assert_eq!(err.to_string(), "Expected a digit after `+` at 1:5");
+        assert!(parse("a = +x\n").is_err());
+        Ok(())
+    }
+
+    #[test]
+    fn triple_quoted_strings() -> Result<()> {
+        let content = "query = \"\"\"\nSELECT *\n  FROM t\"\"\" after = 1\nnext = 2\n";
+        let object = parse(content)?;
+        assert_eq!(object.get_string("query")?, "SELECT *\n  FROM t");
+        assert_eq!(*object.get_int("after")?, 1);
+
+        let err = parse(format!("{}bad 3\n", content)).unwrap_err();
+        assert!(err.to_string().contains("at 5:5-6"), "{}", err);
+
+        let inline = parse("s = \"\"\"one \"quoted\" line\"\"\"\n")?;
+        assert_eq!(inline.get_string("s")?, "one \"quoted\" line");
+
+        assert_round_trip(content)?;
+        assert!(parse("s = \"\"\"never closed\n").is_err());
+        Ok(())
+    }
+
+    #[test]
+    fn keyless_nested_object_errors() {
+        let err = parse("outer = { { } }\n").unwrap_err();
+        assert_eq!(err.kind(), std::io::ErrorKind::InvalidData);
+        assert!(
+            err.to_string()
+                .starts_with("Expected a key or `}`, found `{` at 1:11-12"),
+            "{}",
+            err
+        );
+    }
+
+    #[test]
+    fn cool_macro_builds_nested_documents() -> Result<()> {
+        let empty = cool! {};
+        assert_eq!(empty, CoolDataObject::new());
+
+        let object = cool! {
+            flag = true,
+            nothing = CoolDataType::Null,
+            matrix = [[1, 2], [], [{ x = 1.5 }]],
+        };
+        assert_eq!(
+            object,
+            parse("flag = true\nnothing = null\nmatrix = [[1, 2], [], [{ x = 1.5 }]]\n")?
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn path_entry_upserts() -> Result<()> {
+        let mut object = CoolDataObject::new();
+        object.path_entry("a.b.c")?.or_insert(CoolDataType::Int(1));
+        assert_eq!(object, cool! { a = { b = { c = 1 } } });
+
+        for _ in 0..2 {
+            object
+                .path_entry("a.b.c")?
+                .and_modify(|value| {
+                    if let CoolDataType::Int(count) = value {
+                        *count += 10;
+                    }
+                })
+                .or_insert(CoolDataType::Int(0));
+        }
+        assert_eq!(*object.get_path("a.b.c")?, CoolDataType::Int(21));
+
+        object.path_entry("a.x.y")?.and_modify(|_| unreachable!());
+        assert!(object.is_absent("a.x"));
+
+        assert!(object.path_entry("a.b.c.d").is_err());
+        Ok(())
+    }
+
+    #[test]
+    fn hash_map_conversions() -> Result<()> {
+        use std::collections::HashMap;
+
+        let mut map = HashMap::new();
+        map.insert("port".to_string(), CoolDataType::Int(8080));
+        map.insert("host".to_string(), CoolDataType::from("localhost"));
+
+        let object = CoolDataObject::from(map.clone());
+        assert_eq!(*object.get_int("port")?, 8080);
+        assert_eq!(object.iter().count(), 2);
+        assert_eq!(object.as_map(), &map);
+        assert_eq!(object.into_inner(), map);
+        Ok(())
+    }
+
+    #[test]
+    fn list_to_tuple() -> Result<()> {
+        let object = parse("point = [1.0, 2.5, -3.0]\nentry = [\"id\", 7]\n")?;
+
+        let (x, y, z): (f64, f64, f64) = object.get_list("point")?.try_into()?;
+        assert_eq!((x, y, z), (1.0, 2.5, -3.0));
+        let (name, id): (String, i32) = object.get_list("entry")?.try_into()?;
+        assert_eq!((name.as_str(), id), ("id", 7));
+
+        let err = <(f32, f32)>::try_from(object.get_list("point")?).unwrap_err();
+        assert_eq!(err.to_string(), "Expected a list of 2 elements, got 3.");
+        let err = <(i32, i32)>::try_from(object.get_list("entry")?).unwrap_err();
+        assert_eq!(err.to_string(), "Index 0: Value is not a i32.");
+        Ok(())
+    }
+
+    #[test]
+    fn walk_leaves() -> Result<()> {
+        let mut object = parse(
+            "name = \"app\"\nserver = {\nport = 8080\ncredentials = {\npassword = \"hunter2\"\n}\n}\ntags = [\"a\", \"b\"]\n",
+        )?;
+
+        let mut paths = Vec::new();
+        object.walk(|path, _| paths.push(path.join(".")));
+        paths.sort();
+        assert_eq!(
+            paths,
+            [
+                "name",
+                "server.credentials.password",
+                "server.port",
+                "tags.0",
+                "tags.1"
+            ]
+        );
+
+        object.walk_mut(|path, value| {
+            if path.last().is_some_and(|key| key == "password") {
+                *value = CoolDataType::from("***");
+            }
+        });
+        assert_eq!(
+            object.get_path("server.credentials.password")?,
+            &CoolDataType::from("***")
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn tokenizer_warnings() -> Result<()> {
+        use crate::lexer::Tokenizer;
+
+        let mut tokenizer = Tokenizer::new("a = 1  \nb = 2\n");
+        tokenizer.tokenize()?;
+        let warnings = tokenizer.warnings();
+        assert_eq!(warnings.len(), 1);
+        assert_eq!(warnings[0].to_string(), "Trailing whitespace at 1:6-8");
+
+        let mut tokenizer = Tokenizer::new("key\t= 1\n\tnested = \n");
+        tokenizer.tokenize()?;
+        let messages: Vec<String> = tokenizer.warnings().iter().map(|w| w.to_string()).collect();
+        assert_eq!(
+            messages,
+            [
+                "Tab used for alignment at 1:4-5",
+                "Trailing whitespace at 2:10-11",
+                "Empty assignment at 2:9-10"
+            ]
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn flatten_round_trip() -> Result<()> {
+        let object = parse("a = {\nb = 1\nc = {\nd = \"x\"\n}\n}\ne = 2\n")?;
+        let flat = object.flatten();
+        assert_eq!(flat.get_int("a.b")?, &1);
+        assert_eq!(flat.get_string("a.c.d")?, "x");
+        assert_eq!(flat.unflatten()?, object);
+
+        let object = parse("ports = [80, 443]\nusers = [{\nname = \"root\"\n}]\nempty = []\n")?;
+        let flat = object.flatten();
+        assert_eq!(flat.get_int("ports.1")?, &443);
+        assert_eq!(flat.get_string("users.0.name")?, "root");
+        assert_eq!(flat.unflatten()?, object);
+
+        let mut conflicting = CoolDataObject::new();
+        conflicting.add_field("a".to_string(), 1.into());
+        conflicting.add_field("a.b".to_string(), 2.into());
+        assert!(conflicting.unflatten().is_err());
+        Ok(())
+    }
+
+    #[test]
+    fn compact_fields() -> Result<()> {
+        let mut object = parse(
+            "name = \"app\"\nmissing = null\nempty = {}\nnested = {\ngone = null\n}\nports = [80]\nnone = []\n",
+        )?;
+
+        let mut nulls_only = object.clone();
+        nulls_only.compact(true, false);
+        assert!(nulls_only.is_absent("missing"));
+        assert!(nulls_only.get_object("empty")?.iter().next().is_none());
+        assert!(nulls_only.is_absent("nested.gone"));
+
+        object.compact(true, true);
+        assert_eq!(object, parse("name = \"app\"\nports = [80]\n")?);
+        Ok(())
+    }
+
+    #[test]
+    fn diff_objects() -> Result<()> {
+        let base = parse("name = \"app\"\nserver = {\nport = 80\nhost = \"a\"\n}\ntags = [\"x\", \"y\"]\ndebug = true\n")?;
+        let modified = parse(
+            "name = \"app\"\nserver = {\nport = 8080\nhost = \"a\"\n}\ntags = [\"x\"]\nlevel = 2\n",
+        )?;
+
+        assert_eq!(
+            base.diff(&modified),
+            [
+                Change::Changed {
+                    path: "server.port".to_string(),
+                    from: 80.into(),
+                    to: 8080.into(),
+                },
+                Change::Removed {
+                    path: "tags.1".to_string(),
+                    value: "y".into(),
+                },
+                Change::Removed {
+                    path: "debug".to_string(),
+                    value: true.into(),
+                },
+                Change::Added {
+                    path: "level".to_string(),
+                    value: 2.into(),
+                },
+            ]
+        );
+        assert!(base.diff(&base).is_empty());
+        Ok(())
+    }
+
+    #[test]
+    fn multiple_documents() -> Result<()> {
+        let documents = parse_multi("name = \"a\"\n---\nname = \"b\"\nport = 80\n---\n")?;
+        assert_eq!(documents.len(), 2);
+        assert_eq!(documents[0], parse("name = \"a\"\n")?);
+        assert_eq!(documents[1], parse("name = \"b\"\nport = 80\n")?);
+
+        let documents = parse_multi("text = \"\"\"\n---\n\"\"\"\n")?;
+        assert_eq!(documents.len(), 1);
+        assert_eq!(documents[0].get_string("text")?, "---\n");
+        Ok(())
+    }
+
+    #[test]
+    fn type_names() {
+        let values: [(CoolDataType, &str); 7] = [
+            (1.into(), "int"),
+            (1.5.into(), "float"),
+            ("a".into(), "string"),
+            (CoolDataObject::new().into(), "object"),
+            (CoolDataList::new().into(), "list"),
+            (true.into(), "bool"),
+            (CoolDataType::Null, "null"),
+        ];
+        for (value, name) in values {
+            assert_eq!(value.type_name(), name);
+        }
+
+        assert!(CoolDataType::from(1).is_int());
+        assert!(!CoolDataType::from(1).is_float());
+        assert!(CoolDataType::from("a").is_string());
+        assert!(CoolDataType::from(CoolDataList::new()).is_list());
+        assert!(!CoolDataType::Null.is_object());
+    }
+
+    #[test]
+    fn group_digits() {
+        let object = cool! {
+            population = 1000000,
+            balance = -12345,
+            ratio = 1234.5,
+            small = 999,
+        };
+        let options = SerializeOptions::new().group_digits(',');
+        assert_eq!(
+            object.to_string_with(&options),
+            "population = 1,000,000\nbalance = -12,345\nratio = 1,234.5\nsmall = 999\n"
+        );
+        assert_eq!(
+            object.to_string_with(&SerializeOptions::new()),
+            object.to_string()
+        );
+    }
+
+    #[test]
+    fn into_accessors() -> Result<()> {
+        let object = parse("server = {\nport = 80\n}\nname = \"app\"\n")?;
+        let mut fields = object.into_iter();
+
+        let (_, server) = fields.next().unwrap();
+        let server = server.into_object()?;
+        assert_eq!(server.get_field("port")?.clone().into_int()?, 80);
+
+        let (_, name) = fields.next().unwrap();
+        let err = name.clone().into_list().unwrap_err();
+        assert_eq!(err.to_string(), "Expected list, got string.");
+        assert_eq!(name.into_string()?, "app");
+        Ok(())
+    }
+
+    #[test]
+    fn map_view() -> Result<()> {
+        let object = parse("host = \"localhost\"\nport = 8080\ndebug = false\n")?;
+        let map = object.as_map();
+
+        assert_eq!(map.len(), 3);
+        assert!(map.contains_key("port"));
+        assert!(!map.contains_key("missing"));
+        assert_eq!(map.get("host"), Some(&CoolDataType::from("localhost")));
+        let mut keys: Vec<&String> = map.keys().collect();
+        keys.sort();
+        assert_eq!(keys, ["debug", "host", "port"]);
+        assert_eq!(map.values().filter(|value| value.is_bool()).count(), 1);
+        Ok(())
+    }
+
+    #[test]
+    fn case_insensitive_lookup() -> Result<()> {
+        let object = parse("Port = 8080\nhost = \"a\"\nHOST = \"b\"\n")?;
+
+        assert_eq!(object.get_field_ci("port")?, &CoolDataType::Int(8080));
+        assert_eq!(
+            object.get_field_ci("user").unwrap_err().to_string(),
+            "Unknown field \"user\""
+        );
+        assert_eq!(
+            object.get_field_ci("Host").unwrap_err().to_string(),
+            "Field \"Host\" is ambiguous, it matches both \"host\" and \"HOST\""
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn stream_list_elements() -> Result<()> {
+        let input = "[\n1, \"two, three\"\n{\nx = [4, 5]\n}\n[6]\n]\n";
+        let values = parse_list_stream(input.as_bytes()).collect::<Result<Vec<_>>>()?;
+        let expected = parse(format!("list = {}", input))?;
+        assert_eq!(&CoolDataList(values), expected.get_list("list")?);
+
+        let crlf = input.replace('\n', "\r\n");
+        let values = parse_list_stream(crlf.as_bytes()).collect::<Result<Vec<_>>>()?;
+        assert_eq!(&CoolDataList(values), expected.get_list("list")?);
+        let values = parse_list_stream("[1\r\n,2]".as_bytes()).collect::<Result<Vec<_>>>()?;
+        assert_eq!(values, [CoolDataType::Int(1), CoolDataType::Int(2)]);
+
+        let mut unclosed = parse_list_stream("[1, 2".as_bytes());
+        assert_eq!(unclosed.next().unwrap()?, CoolDataType::Int(1));
+        assert!(unclosed.next().unwrap().is_err());
+        assert!(unclosed.next().is_none());
+
+        let input = r#"["""""", """a""", """"b"""]"#;
+        let values = parse_list_stream(input.as_bytes()).collect::<Result<Vec<_>>>()?;
+        assert_eq!(values, ["", "a", "\"b"].map(CoolDataType::from));
+        let input = r#"["""a"""", 1]"#;
+        assert!(parse_list_stream(input.as_bytes())
+            .collect::<Result<Vec<_>>>()
+            .is_err());
+        assert!(parse(format!("list = {}", input)).is_err());
+
+        let values = parse_list_stream("[1] # done\n\n".as_bytes()).collect::<Result<Vec<_>>>()?;
+        assert_eq!(values, [CoolDataType::Int(1)]);
+        let mut trailing = parse_list_stream("[1, 2] 3".as_bytes());
+        assert_eq!(trailing.next().unwrap()?, CoolDataType::Int(1));
+        assert_eq!(trailing.next().unwrap()?, CoolDataType::Int(2));
+        let err = trailing.next().unwrap().unwrap_err();
+        assert_eq!(err.to_string(), "Unexpected '3' after the closing `]`");
+        assert!(trailing.next().is_none());
+        Ok(())
+    }
+
+    #[test]
+    fn optional_paths() -> Result<()> {
+        let object = parse("server = {\ntls = {\ncert = \"a.pem\"\n}\nports = [80]\n}\n")?;
+
+        assert_eq!(
+            object.try_path("server.tls.cert"),
+            Some(&CoolDataType::from("a.pem"))
+        );
+        assert_eq!(
+            object.try_path("server.ports.0"),
+            Some(&CoolDataType::Int(80))
+        );
+        assert_eq!(object.try_path("server.tls.key"), None);
+        assert_eq!(object.try_path("client.tls.cert"), None);
+        Ok(())
+    }
+
+    #[test]
+    fn set_paths() -> Result<()> {
+        let mut object = CoolDataObject::new();
+        object.set_path("a.b.c", 1.into())?;
+        assert_eq!(object, parse("a = {\nb = {\nc = 1\n}\n}\n")?);
+
+        object.set_path("a.b.c", 2.into())?;
+        object.set_path("a.d", "x".into())?;
+        assert_eq!(object.get_path("a.b.c")?, &CoolDataType::Int(2));
+        assert_eq!(object.get_path("a.d")?, &CoolDataType::from("x"));
+
+        let mut object = parse("a = 1\n")?;
+        let err = object.set_path("a.b", 2.into()).unwrap_err();
+        assert_eq!(
+            err.to_string(),
+            "Field \"a\" in path \"a.b\" is not an object."
+        );
+        assert_eq!(object.get_int("a")?, &1);
+        Ok(())
+    }
+
+    #[test]
+    fn tokens_borrow_source() -> Result<()> {
+        use crate::lexer::{TokenType, Tokenizer};
+        use std::borrow::Cow;
+
+        let tokens = Tokenizer::new("name = \"app\"\nport = +80\n").tokenize()?;
+        assert!(matches!(
+            &tokens[0].0,
+            TokenType::Ident(Cow::Borrowed("name"))
+        ));
+        assert!(matches!(
+            &tokens[2].0,
+            TokenType::String(Cow::Borrowed("app"))
+        ));
+        assert!(matches!(&tokens[6].0, TokenType::Int(Cow::Borrowed("80"))));
+
+        let tokens = Tokenizer::new("text = \"\"\"\r\na\r\nb\"\"\"").tokenize()?;
+        assert!(matches!(&tokens[2].0, TokenType::String(Cow::Owned(text)) if text == "a\nb"));
+
+        let owned: Vec<_> = tokens.into_iter().map(|token| token.into_owned()).collect();
+        assert!(matches!(&owned[0].0, TokenType::Ident(Cow::Owned(name)) if name == "text"));
+        Ok(())
+    }
+
+    #[test]
+    fn reuse_tokenizer() -> Result<()> {
+        use crate::lexer::Tokenizer;
+
+        let mut tokenizer = Tokenizer::new("a = 1\n");
+        assert_eq!(tokenizer.tokenize()?.len(), 4);
+        assert_eq!(tokenizer.tokenize()?.len(), 0);
+
+        tokenizer.reset("b = [1, 2]\n");
+        assert_eq!(tokenizer.tokenize()?.len(), 8);
+        tokenizer.reset("c = 3");
+        let tokens = tokenizer.tokenize()?;
+        assert_eq!(tokens.len(), 3);
+        assert_eq!(tokens[0].loc().to_string(), "1:1");
+        Ok(())
+    }
+
+    #[test]
+    fn tokenizer_limits() -> Result<()> {
+        use crate::lexer::{Tokenizer, TokenizerOptions};
+
+        let options = TokenizerOptions::new().max_string_len(4);
+        let mut tokenizer =
+            Tokenizer::with_options("a = \"abcd\"\nb = \"abcde\"\n", options.clone());
+        let err = tokenizer.tokenize().unwrap_err();
+        assert_eq!(
+            err.to_string(),
+            "String at 2:5 exceeds the maximum length of 4 bytes"
+        );
+        let mut tokenizer = Tokenizer::with_options("a = \"\"\"\nabcde\"\"\"", options);
+        assert!(tokenizer.tokenize().is_err());
+
+        let options = TokenizerOptions::new().max_input_bytes(8);
+        let err = Tokenizer::with_options("name = \"app\"\n", options.clone())
+            .tokenize()
+            .unwrap_err();
+        assert_eq!(
+            err.to_string(),
+            "Input of 13 bytes exceeds the maximum of 8 bytes"
+        );
+        assert_eq!(
+            Tokenizer::with_options("a = 1\n", options)
+                .tokenize()?
+                .len(),
+            4
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn comments_survive_load_save() -> Result<()> {
+        use crate::{lexer::Tokenizer, parser::Parser};
+
+        let content =
+            "# header\nport = 8080 # default port\nserver = {\nhost = \"a\" # primary\n}\n";
+        let load = |content: &str| -> Result<CoolDataObject> {
+            let tokens = Tokenizer::new(content).tokenize()?;
+            Parser::new(tokens).preserve_comments(true).parse()
+        };
+        let object = load(content)?;
+        assert_eq!(object.field_comment("port"), Some("default port"));
+        assert_eq!(
+            object.get_object("server")?.field_comment("host"),
+            Some("primary")
+        );
+
+        let dir = TempDir::new("comments_survive_load_save");
+        let path = dir.path("comments.cool");
+        save_to_file(path.to_str().unwrap(), &object)?;
+        let saved = std::fs::read_to_string(&path)?;
+        assert_eq!(
+            saved,
+            "port = 8080 # default port\nserver = {\nhost = \"a\" # primary\n}\n"
+        );
+        assert_eq!(load(&saved)?.field_comment("port"), Some("default port"));
+
+        let discarded = parse(content)?;
+        assert_eq!(discarded.field_comment("port"), None);
+        assert_eq!(discarded, object);
+        Ok(())
+    }
+
+    #[test]
+    fn char_values() -> Result<()> {
+        let object = parse("sep = ','\nkeys = ['q', 'é']\n")?;
+        assert_eq!(object.get_char("sep")?, &',');
+        assert_eq!(object.get_list("keys")?.char_at(1)?, &'é');
+        assert!(object.get_string("sep").is_err());
+        assert_eq!(object.to_string(), "sep = ','\nkeys = ['q', 'é']\n");
+        assert_round_trip("sep = ','\n")?;
+
+        let err = parse("sep = ''\n").unwrap_err();
+        assert_eq!(
+            err.to_string(),
+            "Character literal must hold exactly one character, got \"\" at 1:7"
+        );
+        assert!(parse("sep = 'ab'\n").is_err());
+        assert!(parse("sep = 'a\n").is_err());
+
+        let object = parse("a = '\\''\nb = '\\\\'\nc = '\\n'\nd = '\\t'\ne = '\"'\n")?;
+        assert_eq!(object.get_char("a")?, &'\'');
+        assert_eq!(object.get_char("b")?, &'\\');
+        assert_eq!(object.get_char("c")?, &'\n');
+        assert_eq!(object.get_char("d")?, &'\t');
+        assert_eq!(object.get_char("e")?, &'"');
+        for c in ['\'', '\\', '\n', '\t', '\u{301}', '"'] {
+            let mut object = CoolDataObject::new();
+            object.add_field("c".into(), c.into());
+            assert_eq!(parse(object.to_string())?, object, "{:?}", c);
+        }
+        assert_eq!(CoolDataType::Char('\'').to_string(), "'\\''");
+        let streamed = parse_list_stream("['\\'', ',']".as_bytes()).collect::<Result<Vec<_>>>()?;
+        assert_eq!(
+            streamed,
+            [CoolDataType::Char('\''), CoolDataType::Char(',')]
+        );
+        Ok(())
+    }
+
+    #[cfg(feature = "base64")]
+    #[test]
+    fn bytes_round_trip() -> Result<()> {
+        let object = parse("key = b64\"AAEC/w==\"\nblobs = [b64\"aGk=\"]\n")?;
+        assert_eq!(object.get_bytes("key")?, &[0, 1, 2, 255]);
+        assert_eq!(object.get_list("blobs")?.bytes_at(0)?, b"hi");
+
+        let dir = TempDir::new("bytes_round_trip");
+        let path = dir.path("bytes.cool");
+        save_to_file(path.to_str().unwrap(), &object)?;
+        assert_eq!(load_from_file(path.to_str().unwrap())?, object);
+
+        let err = parse("key = b64\"not base64!\"\n").unwrap_err();
+        assert!(err.to_string().starts_with("Invalid base64 at 1:7"));
+        Ok(())
+    }
+
+    #[test]
+    fn env_interpolation() -> Result<()> {
+        let lookup = |name: &str| (name == "DB_HOST").then(|| "db.local".to_string());
+        let mut object = parse(
+            "url = \"postgres://${DB_HOST}/app\"\nnested = {\nport = \"${DB_PORT:-5432}\"\n}\nprice = \"$$5 or $x\"\n",
+        )?;
+        object.interpolate_with(lookup)?;
+        assert_eq!(object.get_string("url")?, "postgres://db.local/app");
+        assert_eq!(object.get_path("nested.port")?, &CoolDataType::from("5432"));
+        assert_eq!(object.get_string("price")?, "$5 or $x");
+
+        let mut missing = parse("url = \"${DB_USER}\"\n")?;
+        let err = missing.interpolate_with(lookup).unwrap_err();
+        assert_eq!(
+            err.to_string(),
+            "Undefined environment variable \"DB_USER\""
+        );
+
+        let mut object = parse("path = \"${PATH:-}\"\n")?;
+        object.interpolate_env()?;
+        assert_eq!(
+            object.get_string("path")?,
+            &std::env::var("PATH").unwrap_or_default()
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn include_directive() -> Result<()> {
+        let dir = TempDir::new("include_directive");
+        std::fs::create_dir_all(dir.path("shared"))?;
+        std::fs::write(
+            dir.path("shared/common.cool"),
+            "host = \"localhost\"\nport = 80\n",
+        )?;
+        std::fs::write(
+            dir.path("main.cool"),
+            "name = \"app\"\n@include \"shared/common.cool\"\nport = 8080\n",
+        )?;
+
+        let object = load_from_file(dir.path("main.cool").to_str().unwrap())?;
+        assert_eq!(
+            object,
+            parse("name = \"app\"\nhost = \"localhost\"\nport = 8080\n")?
+        );
+        assert!(parse("@include \"shared/common.cool\"\n").is_err());
+
+        std::fs::write(dir.path("a.cool"), "@include \"b.cool\"\n")?;
+        std::fs::write(dir.path("b.cool"), "@include \"a.cool\"\n")?;
+        let err = load_from_file(dir.path("a.cool").to_str().unwrap()).unwrap_err();
+        assert!(err.to_string().starts_with("Include cycle: "));
+        Ok(())
+    }
+
+    #[test]
+    fn schema_validation() -> Result<()> {
+        let schema = Schema::new()
+            .required("name", FieldKind::String)
+            .optional("debug", FieldKind::Bool)
+            .required(
+                "server",
+                FieldKind::Object(
+                    Schema::new()
+                        .required("host", FieldKind::String)
+                        .required("port", FieldKind::Int),
+                ),
+            );
+
+        let valid = parse("name = \"app\"\nserver = {\nhost = \"a\"\nport = 80\n}\n")?;
+        assert_eq!(schema.validate(&valid), Ok(()));
+
+        let invalid = parse("name = 1\ndebug = true\nserver = {\nhost = \"a\"\n}\n")?;
+        let errors: Vec<String> = schema
+            .validate(&invalid)
+            .unwrap_err()
+            .iter()
+            .map(|err| err.to_string())
+            .collect();
+        assert_eq!(
+            errors,
+            [
+                "name: Expected string, got int",
+                "server.port: Missing required field"
+            ]
+        );
+        Ok(())
+    }
+
+    #[cfg(feature = "toml")]
+    #[test]
+    fn toml_output() -> Result<()> {
+        let object = parse(
+            "title = \"\"\"\nsay \"hi\"\n\"\"\"\nratio = 0.5\nports = [80, 443]\nserver = {\nhost = \"localhost\"\ntls = {\nenabled = true\n}\n}\n\"odd key\" = 'x'\n",
+        )?;
+        assert_eq!(
+            object.to_toml_string()?,
+            "title = \"say \\\"hi\\\"\\n\"\nratio = 0.5\nports = [80, 443]\n\"odd key\" = \"x\"\n\n[server]\nhost = \"localhost\"\n\n[server.tls]\nenabled = true\n"
+        );
+
+        let err = parse("a = [1, null]\n")?.to_toml_string().unwrap_err();
+        assert_eq!(err.to_string(), "TOML can't represent the null at \"a.1\"");
+
+        let object = parse("max = 9223372036854775807u\n")?;
+        assert_eq!(object.to_toml_string()?, "max = 9223372036854775807\n");
+        let err = parse("max = 18446744073709551615u\n")?
+            .to_toml_string()
+            .unwrap_err();
+        assert_eq!(err.to_string(), "TOML can't represent the uint at \"max\"");
+        Ok(())
+    }
+
+    #[test]
+    fn sort_and_dedup_lists() -> Result<()> {
+        let mut numbers = parse("a = [3, 1.5, -2, 10, 0.0]\n")?.get_list("a")?.clone();
+        numbers.sort();
+        assert_eq!(
+            numbers,
+            parse("a = [-2, 0.0, 1.5, 3, 10]\n")?.get_list("a")?.clone()
+        );
+
+        let mut mixed = parse("a = [\"b\", 2, null, \"a\", true]\n")?
+            .get_list("a")?
+            .clone();
+        mixed.sort();
+        assert_eq!(mixed.to_string(), "[null, true, 2, \"a\", \"b\"]");
+
+        let mut tags = parse("a = [\"x\", \"y\", \"x\", \"x\"]\n")?
+            .get_list("a")?
+            .clone();
+        tags.dedup();
+        assert_eq!(tags.0.len(), 3);
+        tags.sort();
+        tags.dedup();
+        assert_eq!(tags, parse("a = [\"x\", \"y\"]\n")?.get_list("a")?.clone());
+        assert!(tags.contains(&"y".into()));
+        assert!(!tags.contains(&"z".into()));
+        Ok(())
+    }
+
+    #[test]
+    fn list_combinators() -> Result<()> {
+        let object = parse("a = [1, null, 2, null, \"x\"]\n")?;
+
+        let mut list = object.get_list("a")?.clone();
+        list.retain(|value| !value.is_null());
+        assert_eq!(list, parse("a = [1, 2, \"x\"]\n")?.get_list("a")?.clone());
+
+        list.map_in_place(|value| match value {
+            CoolDataType::Int(val) => CoolDataType::Int(val + 1),
+            other => other,
+        });
+        assert_eq!(list, parse("a = [2, 3, \"x\"]\n")?.get_list("a")?.clone());
+        Ok(())
+    }
+
+    #[test]
+    fn canonical_bytes() -> Result<()> {
+        use std::hash::{DefaultHasher, Hash, Hasher};
+
+        let a = parse("name = \"app\"\nratio = -0.0\nserver = {\nport = 80\nhost = \"x\"\n}\ntags = [1, 2.50]\n")?;
+        let b = parse("tags = [1, 2.5] # sorted later\nserver = {\nhost = \"x\"\nport = 80\n}\nratio = 0.0\nname = \"app\"\n")?;
+
+        let bytes = a.to_canonical_bytes();
+        assert_eq!(bytes, b.to_canonical_bytes());
+        assert_eq!(
+            String::from_utf8(bytes.clone()).unwrap(),
+            "{name=\"app\",ratio=0.0,server={host=\"x\",port=80},tags=[1,2.5]}"
+        );
+
+        let hash = |bytes: &[u8]| {
+            let mut hasher = DefaultHasher::new();
+            bytes.hash(&mut hasher);
+            hasher.finish()
+        };
+        assert_eq!(hash(&bytes), hash(&b.to_canonical_bytes()));
+        assert_ne!(bytes, parse("name = \"app\"\n")?.to_canonical_bytes());
+
+        let mut escapes = CoolDataObject::new();
+        escapes.add_field("a\tb".into(), "it's \"\u{7}\"".into());
+        escapes.add_field("c".into(), CoolDataType::Char('\''));
+        assert_eq!(
+            String::from_utf8(escapes.to_canonical_bytes()).unwrap(),
+            r#"{"a\tb"="it's \"\u{7}\"",c='\''}"#
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn top_level_values() -> Result<()> {
+        assert_eq!(
+            parse_value("[1, 2, 3]\n")?,
+            CoolDataType::from(vec![1, 2, 3])
+        );
+        assert_eq!(
+            parse_value("\"hello\"")?,
+            CoolDataType::String("hello".into())
+        );
+        assert_eq!(parse_value("\n42\n")?, CoolDataType::Int(42));
+        assert_eq!(parse_value("2.5")?, CoolDataType::Float(2.5));
+        assert_eq!(parse_value("true")?, CoolDataType::Bool(true));
+        assert_eq!(parse_value("null")?, CoolDataType::Null);
+        assert_eq!(
+            parse_value("{\na = 1\n}")?,
+            CoolDataType::Object(parse("a = 1\n")?)
+        );
+        assert_eq!(
+            parse_value("# root\na = 1\nb = [2]\n")?,
+            CoolDataType::Object(parse("a = 1\nb = [2]\n")?)
+        );
+        assert_eq!(
+            parse_value("")?,
+            CoolDataType::Object(CoolDataObject::new())
+        );
+
+        assert!(parse_value("1 2").is_err());
+        assert!(parse_value("[1, 2] x = 3").is_err());
+        Ok(())
+    }
+
+    #[test]
+    fn stray_tokens_error() -> Result<()> {
+        for content in [
+            "= 1\n",
+            ", x = 1\n",
+            "} \n",
+            "] \n",
+            "x = 1\n42\n",
+            "x = =\n",
+            "x = }\n",
+        ] {
+            let err = parse(content).unwrap_err();
+            assert_eq!(err.kind(), std::io::ErrorKind::InvalidData, "{:?}", content);
+            assert!(err.to_string().contains(" at "), "{}", err);
+        }
+
+        let err = parse("x = 1\n= 2\n").unwrap_err();
+        assert!(err
+            .to_string()
+            .starts_with("Expected a key or directive, found `=`"));
+        assert!(parse_value("=").is_err());
+        Ok(())
+    }
+
+    #[test]
+    fn uint_values() -> Result<()> {
+        let object = parse(
+            "max = 18446744073709551615\nsize = 100u\nabove = 9223372036854775808\n\
+             big = 3000000000\nlargest = 9223372036854775807\nsmall = 100\n",
+        )?;
+        assert_eq!(object.get_uint("max")?, &u64::MAX);
+        assert_eq!(object.get_uint("size")?, &100);
+        assert_eq!(object.get_uint("above")?, &(i64::MAX as u64 + 1));
+        assert_eq!(object.get_int("big")?, &3_000_000_000);
+        assert_eq!(object.get_int("largest")?, &i64::MAX);
+        assert_eq!(object.get_int("small")?, &100);
+        assert!(object.get_int("size").is_err());
+        assert_eq!(parse("x = -3000000000\n")?.get_int("x")?, &-3_000_000_000);
+        assert_eq!(object.get_int_or("big", 0)?, 3_000_000_000);
+        assert!(Schema::new()
+            .required("big", FieldKind::Int)
+            .validate(&object)
+            .is_ok());
+        let big = object.get_field("big")?;
+        assert_eq!(i64::try_from(big)?, 3_000_000_000);
+        let err = i32::try_from(big).unwrap_err();
+        assert_eq!(err.to_string(), "Value is out of range for i32.");
+        let err = i32::from_cool_value(big).unwrap_err();
+        assert_eq!(err.to_string(), "Value 3000000000 is out of range for i32.");
+        assert_eq!(i32::from_cool_value(object.get_field("small")?)?, 100);
+
+        assert_eq!(parse(object.to_string())?, object);
+        assert!(object.to_string().contains("size = 100u"));
+
+        let list = parse_value("[1u, 18446744073709551615]")?.into_list()?;
+        assert_eq!(list.uint_at(0)?, &1);
+        assert_eq!(list.uint_at(1)?, &u64::MAX);
+
+        assert!(parse("x = -1u\n").is_err());
+        if !cfg!(feature = "bigint") {
+            assert!(parse("x = 18446744073709551616\n").is_err());
+            assert!(parse("x = -9223372036854775809\n").is_err());
+        }
+        Ok(())
+    }
+
+    #[test]
+    fn inline_short_lists() -> Result<()> {
+        let object = parse("small = [1, 2, 3]\nempty = []\n")?;
+        assert_eq!(object.to_string(), "small = [1, 2, 3]\nempty = []\n");
+
+        let long: Vec<i64> = (1000..1020).collect();
+        let mut object = CoolDataObject::new();
+        object.add_field("long".into(), CoolDataType::from(long));
+        object.add_field("nested".into(), CoolDataType::from(vec![vec![1]]));
+        let text = object.to_string();
+        assert!(text.starts_with("long = [\n1000,\n1001,\n"));
+        assert!(text.contains("nested = [\n[1],\n]"));
+        assert_eq!(parse(text)?, object);
+
+        let object = parse("a = [1, 2]\n")?;
+        let options = SerializeOptions::new().inline_width(0);
+        assert_eq!(object.to_string_with(&options), "a = [\n1,\n2,\n]\n");
+        let options = SerializeOptions::new().inline_width(6);
+        assert_eq!(object.to_string_with(&options), "a = [1, 2]\n");
+        let options = SerializeOptions::new().inline_width(5);
+        assert_eq!(object.to_string_with(&options), "a = [\n1,\n2,\n]\n");
+        Ok(())
+    }
+
+    #[test]
+    fn lenient_ints() -> Result<()> {
+        let object = parse("a = 3\nb = 3.0\nc = 3.5\nd = -2.0\ne = \"3\"\nf = nan\ng = 5u\n")?;
+        assert_eq!(object.get_int_lenient("a")?, 3);
+        assert_eq!(object.get_int_lenient("b")?, 3);
+        assert!(object.get_int_lenient("c").is_err());
+        assert_eq!(object.get_int_lenient("d")?, -2);
+        assert!(object.get_int_lenient("e").is_err());
+        assert!(object.get_int_lenient("f").is_err());
+        assert_eq!(object.get_int_lenient("g")?, 5);
+        assert!(object.get_int("b").is_err());
+        Ok(())
+    }
+
+    #[test]
+    fn reserved_words_and_keywords() -> Result<()> {
+        use crate::lexer::{Token, TokenType, Tokenizer, TokenizerOptions};
+
+        let options = TokenizerOptions::new().reserved_words(["self"]);
+        let err = Tokenizer::with_options("name = 1\nself = 2\n", options.clone())
+            .tokenize()
+            .unwrap_err();
+        assert_eq!(err.to_string(), "Reserved word `self` at 2:1");
+        assert!(Tokenizer::with_options("selfish = 1\n", options)
+            .tokenize()
+            .is_ok());
+
+        let options = TokenizerOptions::new().allowed_keywords(["when", "true"]);
+        let tokens = Tokenizer::with_options("when = true\n", options).tokenize()?;
+        assert!(matches!(&tokens[0], Token(TokenType::Keyword(word), _) if word == "when"));
+        assert!(matches!(tokens[2], Token(TokenType::Bool(true), _)));
+
+        let err = crate::parser::Parser::new(tokens).parse().unwrap_err();
+        assert!(err
+            .to_string()
+            .starts_with("Expected a key or directive, found `when`"));
+        Ok(())
+    }
+
+    #[cfg(feature = "quickcheck")]
+    #[test]
+    fn arbitrary_round_trip() {
+        fn round_trip(object: CoolDataObject) -> bool {
+            let mut out = Vec::new();
+            save_to_writer(&mut out, &object).unwrap();
+            parse(String::from_utf8(out).unwrap()).is_ok_and(|parsed| parsed == object)
+        }
+
+        quickcheck::QuickCheck::new()
+            .tests(500)
+            .quickcheck(round_trip as fn(CoolDataObject) -> bool);
+    }
+
+    #[test]
+    fn loc_accessors() {
+        use crate::lexer::Loc;
+
+        let loc = Loc::new(3, 5);
+        assert_eq!(loc.to_string(), "3:5");
+        assert_eq!((loc.line(), loc.col()), (3, 5));
+    }
+
+    #[test]
+    fn flag_fields() -> Result<()> {
+        use crate::lexer::Tokenizer;
+        use crate::parser::Parser;
+
+        let content = "verbose\nname = \"x\"\nserver = {\ntls # on\n}\n";
+        let tokens = Tokenizer::new(content).tokenize()?;
+        let object = Parser::new(tokens).flag_fields(true).parse()?;
+        assert_eq!(object.get_bool("verbose")?, &true);
+        assert_eq!(object.get_string("name")?, "x");
+        assert_eq!(object.get_path("server.tls")?, &CoolDataType::Bool(true));
+
+        let tokens = Tokenizer::new(content).tokenize()?;
+        assert!(Parser::new(tokens).parse().is_err());
+        let tokens = Tokenizer::new("verbose 1\n").tokenize()?;
+        assert!(Parser::new(tokens).flag_fields(true).parse().is_err());
+        Ok(())
+    }
+
+    #[test]
+    fn hash_keys() -> Result<()> {
+        use std::collections::HashSet;
+
+        let a = parse("name = \"x\"\nports = [1, 2]\ninner = {\nb = true\nc = null\n}\n")?;
+        let b = parse("inner = {\nc = null\nb = true\n}\nports = [1, 2]\nname = \"x\"\n")?;
+        let mut set = HashSet::new();
+        assert!(set.insert(HashKey::new(a)?));
+        assert!(!set.insert(HashKey::new(b)?));
+        assert!(set.insert(HashKey::new(parse("name = \"y\"\n")?)?));
+        assert_eq!(set.len(), 2);
+
+        assert!(HashKey::new(1.5).is_err());
+        assert!(HashKey::new(parse("a = [1, { b = 2.0 }]\n")?).is_err());
+        assert!(HashKey::new(vec![1, 2]).is_ok());
+        Ok(())
+    }
+
+    #[test]
+    fn missing_values() -> Result<()> {
+        let err = parse("a = 1\nx =").unwrap_err();
+        assert_eq!(err.kind(), std::io::ErrorKind::UnexpectedEof);
+        assert_eq!(
+            err.to_string(),
+            "Expected a value for field \"x\" at 2:4, found end of input"
+        );
+
+        let err = parse("x = }").unwrap_err();
+        assert!(err
+            .to_string()
+            .starts_with("Expected a value for field \"x\", found `}` at 1:5"));
+
+        let err = parse("x =\ny = 1\n").unwrap_err();
+        assert!(err
+            .to_string()
+            .starts_with("Expected a value for field \"x\", found the end of the line"));
+
+        let err = parse("a = {\nb = ,\n}\n").unwrap_err();
+        assert!(err
+            .to_string()
+            .starts_with("Expected a value for field \"b\", found `,`"));
+        Ok(())
+    }
+
+    #[test]
+    fn tab_width() -> Result<()> {
+        use crate::lexer::{Tokenizer, TokenizerOptions};
+
+        let content = "a = {\n\tb = $\n}\n";
+        let err = Tokenizer::new(content).tokenize().unwrap_err();
+        assert_eq!(err.to_string(), "Unexpected character '$' at 2:6");
+
+        let options = TokenizerOptions::new().tab_width(4);
+        let err = Tokenizer::with_options(content, options.clone())
+            .tokenize()
+            .unwrap_err();
+        assert_eq!(err.to_string(), "Unexpected character '$' at 2:9");
+
+        let content = "a = {\n\t\tb = }\n}\n";
+        let tokens = Tokenizer::with_options(content, options.clone()).tokenize()?;
+        let err = crate::parser::Parser::new(tokens).parse().unwrap_err();
+        assert_eq!(
+            err.to_string(),
+            "Expected a value for field \"b\", found `}` at 2:13-14"
+        );
+
+        let tokens = Tokenizer::with_options(content, options.clone()).tokenize()?;
+        let err = crate::parser::Parser::new(tokens)
+            .with_source(content)
+            .tab_width(4)
+            .parse()
+            .unwrap_err();
+        assert_eq!(err.to_string().lines().last(), Some("  | \t\t    ^"));
+
+        let tokens = Tokenizer::with_options("s = \"\t\" c = '\t'", options).tokenize()?;
+        assert_eq!(tokens[2].1.end, crate::lexer::Loc::new(1, 11));
+        assert_eq!(tokens[5].1.end, crate::lexer::Loc::new(1, 22));
+        Ok(())
+    }
+
+    #[test]
+    fn typed_defaults() -> Result<()> {
+        let object = parse("port = 80\nname = 3\nflag = null\n")?;
+        assert_eq!(object.get_int_or("port", 5)?, 80);
+        assert_eq!(object.get_int_or("retries", 5)?, 5);
+        assert_eq!(
+            object.get_string_or("host", "localhost".into())?,
+            "localhost"
+        );
+        assert_eq!(object.get_float_or("ratio", 0.5)?, 0.5);
+        assert_eq!(object.get_uint_or("size", 1)?, 1);
+        assert_eq!(object.get_char_or("sep", ',')?, ',');
+
+        assert!(object.get_string_or("name", "x".into()).is_err());
+        assert!(object.get_int_or("name", 5).is_ok());
+        assert!(object.get_bool_or("flag", false).is_err());
+        assert!(object.get_bool_or("port", false).is_err());
+        Ok(())
+    }
+
+    #[test]
+    fn write_to_writer() -> Result<()> {
+        let object = parse("a = [1, 2]\nb = {\nc = \"x\" # note\nd = [\n{\ne = null\n},\n]\n}\n")?;
+        let mut out = Vec::new();
+        save_to_writer(&mut out, &object)?;
+        assert_eq!(String::from_utf8(out).unwrap(), object.to_string());
+
+        let mut out = Vec::new();
+        let value = object.get_field("a")?;
+        value.write_to(&mut out, &SerializeOptions::new().inline_width(0))?;
+        assert_eq!(out, b"[\n1,\n2,\n]");
+
+        struct Full;
+        impl std::io::Write for Full {
+            fn write(&mut self, _: &[u8]) -> Result<usize> {
+                Err(std::io::Error::new(std::io::ErrorKind::StorageFull, "full"))
+            }
+            fn flush(&mut self) -> Result<()> {
+                Ok(())
+            }
+        }
+        let err = save_to_writer(Full, &object).unwrap_err();
+        assert_eq!(err.kind(), std::io::ErrorKind::StorageFull);
+        Ok(())
+    }
+
+    #[test]
+    fn string_escapes() -> Result<()> {
+        use crate::ser::QuoteStyle;
+
+        let text = "say \"hi\"\tC:\\dir\\";
+        let mut object = CoolDataObject::new();
+        object.add_field("a".into(), text.into());
+        object.add_field("b\"c".into(), "line\nbreak".into());
+        let written = object.to_string();
+        assert_eq!(
+            written,
+            "a = \"say \\\"hi\\\"\\tC:\\\\dir\\\\\"\n\"b\\\"c\" = \"\"\"\nline\nbreak\"\"\"\n"
+        );
+        assert_eq!(parse(written)?, object);
+
+        let options = SerializeOptions::new().quote_style(QuoteStyle::Double);
+        let written = object.to_string_with(&options);
+        assert!(written.ends_with("= \"line\\nbreak\"\n"));
+        assert_eq!(parse(written)?, object);
+
+        let object = parse("path = \"C:\\dir\"\nraw = \"\"\"\na\\n\"\"\"\n")?;
+        assert_eq!(object.get_string("path")?, "C:\\dir");
+        assert_eq!(object.get_string("raw")?, "a\\n");
+        assert!(parse("a = \"open\\\"\n").is_err());
+        Ok(())
+    }
+
+    #[test]
+    fn backslashes_from_before_escapes() -> Result<()> {
+        let object = parse(r#"path = "C:\new\temp\dir""#)?;
+        assert_eq!(object.get_string("path")?, "C:\new\temp\\dir");
+
+        let object = parse("escaped = \"C:\\\\new\"\nraw = \"\"\"C:\\new\"\"\"\n")?;
+        assert_eq!(object.get_string("escaped")?, r"C:\new");
+        assert_eq!(object.get_string("raw")?, r"C:\new");
+        Ok(())
+    }
+
+    #[test]
+    fn partial_parse() -> Result<()> {
+        use crate::lexer::Tokenizer;
+        use crate::parser::Parser;
+
+        let content = "x = 1\n<garbage>\n";
+        let mut tokenizer = Tokenizer::new(content);
+        let tokens: Vec<_> = tokenizer.by_ref().map_while(Result::ok).collect();
+        assert_eq!(tokenizer.position(), 6);
+        assert_eq!(tokenizer.loc().to_string(), "2:1");
+
+        let mut parser = Parser::new(tokens);
+        let (object, stop) = parser.parse_partial();
+        assert_eq!(object, parse("x = 1\n")?);
+        assert_eq!((stop, parser.position()), (4, 4));
+
+        let tokens = Tokenizer::new("x = 1\ny = { z = \n= 2\n").tokenize()?;
+        let (object, stop) = Parser::new(tokens).parse_partial();
+        assert_eq!(object.get_int("x")?, &1);
+        assert!(object.is_absent("y"));
+        assert_eq!(stop, 4);
+
+        let tokens = Tokenizer::new("x = 1\n").tokenize()?;
+        let len = tokens.len();
+        assert_eq!(Parser::new(tokens).parse_partial().1, len);
+        Ok(())
+    }
+
+    #[test]
+    fn comments_in_lists() -> Result<()> {
+        let content = "ports = [ # web\n\n  80, # http\n  # tls below\n  443,\n\n]\nnested = [[1, # one\n2], {\na = 1 # inner\n}]\n";
+        let object = parse(content)?;
+        assert_eq!(
+            object.get_list("ports")?,
+            &CoolDataList(vec![80.into(), 443.into()])
+        );
+        let nested = object.get_list("nested")?;
+        assert_eq!(nested.list_at(0)?, &CoolDataList(vec![1.into(), 2.into()]));
+        assert_eq!(nested.object_at(1)?.get_int("a")?, &1);
+
+        let stream = parse_list_stream("[ # web\n80, # http\n\"#443\", # tls\n]".as_bytes());
+        let values: Vec<CoolDataType> = stream.collect::<Result<_>>()?;
+        assert_eq!(values, vec![80.into(), "#443".into()]);
+        Ok(())
+    }
+
+    #[test]
+    fn rename_fields() -> Result<()> {
+        let mut object = parse("a = 1\nold = \"x\"\nc = 3\n")?;
+        object.set_field_comment("old".into(), "kept");
+        object.rename_field("old", "new")?;
+        assert_eq!(object.to_string(), "a = 1\nnew = \"x\" # kept\nc = 3\n");
+        assert!(object.is_absent("old"));
+
+        let err = object.rename_field("missing", "b").unwrap_err();
+        assert_eq!(
+            CoolError::of(&err),
+            Some(&CoolError::FieldMissing {
+                name: "missing".into()
+            })
+        );
+        let err = object.rename_field("a", "c").unwrap_err();
+        assert_eq!(err.kind(), std::io::ErrorKind::InvalidInput);
+        assert_eq!(object.get_int("a")?, &1);
+        assert_eq!(object.get_int("c")?, &3);
+
+        object.set_case_insensitive(true);
+        assert_eq!(
+            object.rename_field("A", "C").unwrap_err().kind(),
+            std::io::ErrorKind::InvalidInput
+        );
+        object.rename_field("NEW", "New")?;
+        assert_eq!(object.to_string(), "a = 1\nNew = \"x\" # kept\nc = 3\n");
+        assert_eq!(object.get_string("new")?, "x");
+        Ok(())
+    }
+
+    #[test]
+    fn owned_conversions() -> Result<()> {
+        let object = parse("n = 3\nf = 1.5\ns = \"x\"\nb = true\no = {\na = 1\n}\nl = [1]\n")?;
+        let field = |name: &str| object.get_field(name).cloned();
+
+        let n: i64 = field("n")?.try_into()?;
+        let f: f64 = field("f")?.try_into()?;
+        let s: String = field("s")?.try_into()?;
+        let b: bool = field("b")?.try_into()?;
+        let o: CoolDataObject = field("o")?.try_into()?;
+        let l: CoolDataList = field("l")?.try_into()?;
+        assert_eq!((n, f, s.as_str(), b), (3, 1.5, "x", true));
+        assert_eq!(o.get_int("a")?, &1);
+        assert_eq!(l, CoolDataList(vec![1.into()]));
+
+        let err = i64::try_from(field("s")?).unwrap_err();
+        assert_eq!(err.to_string(), "Value is not a i64.");
+        assert!(CoolDataObject::try_from(field("l")?).is_err());
+        Ok(())
+    }
+
+    #[test]
+    fn float_formatting() -> Result<()> {
+        assert_eq!(parse("x = 0.1\n")?.to_string(), "x = 0.1\n");
+        let content = "a = 0.3\nb = 1.1\nc = 3.14159\nd = 100.0\ne = -2.5\nf = 16777216.0\ng = 0.000001\nh = -inf\n";
+        assert_eq!(parse(content)?.to_string(), content);
+        assert_eq!(parse("x = -0.0\n")?.to_string(), "x = -0.0\n");
+
+        for val in [
+            0.1f32,
+            1.0 / 3.0,
+            f32::MAX,
+            f32::MIN_POSITIVE,
+            1e-7,
+            123456.79,
+        ] {
+            let mut object = CoolDataObject::new();
+            object.add_field("x".into(), val.into());
+            assert_eq!(parse(object.to_string())?.get_float("x")?, &val);
+        }
+        Ok(())
+    }
+
+    #[test]
+    fn parse_over_defaults() -> Result<()> {
+        use crate::lexer::Tokenizer;
+        use crate::parser::Parser;
+
+        let mut config = parse("host = \"localhost\"\nport = 80\ntags = [\"a\"]\n")?;
+        let tokens = Tokenizer::new("port = 8080\ntags = [\"b\"]\ndebug = true\n").tokenize()?;
+        Parser::new(tokens).parse_into(&mut config, |key, current, incoming| {
+            match (key, current, incoming) {
+                ("tags", CoolDataType::List(a), CoolDataType::List(b)) => {
+                    CoolDataType::List(CoolDataList([a.0.clone(), b.0.clone()].concat()))
+                }
+                _ => incoming.clone(),
+            }
+        })?;
+
+        assert_eq!(
+            config,
+            parse("host = \"localhost\"\nport = 8080\ntags = [\"a\", \"b\"]\ndebug = true\n")?
+        );
+        assert_eq!(
+            config.to_string(),
+            "host = \"localhost\"\nport = 8080\ntags = [\"a\", \"b\"]\ndebug = true\n"
+        );
+        Ok(())
+    }
+
+    #[cfg(feature = "bigint")]
+    #[test]
+    fn bigint_values() -> Result<()> {
+        let digits = "1234567890123456789012345678901234567890";
+        let object = parse(format!("id = {}\nneg = -{}\nsmall = 5\n", digits, digits))?;
+        assert_eq!(object.get_bigint("id")?.to_string(), digits);
+        assert_eq!(
+            object.get_bigint("neg")?.to_string(),
+            format!("-{}", digits)
+        );
+        assert_eq!(object.get_int("small")?, &5);
+        assert_eq!(
+            object.to_string(),
+            format!("id = {}\nneg = -{}\nsmall = 5\n", digits, digits)
+        );
+        assert_eq!(parse(object.to_string())?, object);
+        assert!(parse("x = 18446744073709551616u\n").is_err());
+        Ok(())
+    }
+
+    #[test]
+    fn double_period_column() {
+        let err = parse("x = 1.2.3\n").unwrap_err();
+        assert_eq!(err.to_string(), "Double period `.` at 1:8");
+        let err = parse("a = 1\nlong = -10.25.5\n").unwrap_err();
+        assert_eq!(err.to_string(), "Double period `.` at 2:14");
+    }
+
+    #[test]
+    fn int_parse_errors() {
+        let err = CoolDataType::int("9223372036854775808").unwrap_err();
+        assert_eq!(
+            err.to_string(),
+            "integer literal too large: \"9223372036854775808\""
+        );
+        let err = CoolDataType::int("-9223372036854775809").unwrap_err();
+        assert_eq!(
+            err.to_string(),
+            "integer literal too large: \"-9223372036854775809\""
+        );
+        let err = CoolDataType::int("12ab").unwrap_err();
+        assert_eq!(err.to_string(), "not a valid integer: \"12ab\"");
+        let err = CoolDataType::uint("99999999999999999999").unwrap_err();
+        assert_eq!(
+            err.to_string(),
+            "integer literal too large: \"99999999999999999999\""
+        );
+        let err = CoolDataType::float("1.2.3").unwrap_err();
+        assert_eq!(err.to_string(), "not a valid float: \"1.2.3\"");
+    }
+
+    #[test]
+    fn tokens_ref() -> Result<()> {
+        use crate::lexer::Tokenizer;
+
+        let mut tokenizer = Tokenizer::new("x = 1\ny = [2, ?]\n");
+        assert!(tokenizer.tokenize().is_err());
+        let expected = Tokenizer::new("x = 1\ny = [2,").tokenize()?;
+        assert_eq!(
+            format!("{:?}", tokenizer.tokens_ref()),
+            format!("{:?}", expected)
+        );
+
+        let mut tokenizer = Tokenizer::new("x = 1\n");
+        assert_eq!(tokenizer.tokenize()?.len(), 4);
+        assert!(tokenizer.tokens_ref().is_empty());
+        Ok(())
+    }
+
+    #[test]
+    fn list_insert_and_swap_remove() -> Result<()> {
+        let mut list = CoolDataList::new();
+        list.push_int(1).push_int(3);
+        list.insert(1, CoolDataType::Int(2))?;
+        list.insert(3, CoolDataType::Int(4))?;
+        assert_eq!(list.to_string(), "[1, 2, 3, 4]");
+        assert_eq!(
+            list.insert(5, CoolDataType::Null).unwrap_err().to_string(),
+            "Index 5 out of bounds."
+        );
+
+        assert_eq!(list.swap_remove(0)?, CoolDataType::Int(1));
+        assert_eq!(list.to_string(), "[4, 2, 3]");
+        assert!(list.swap_remove(3).is_err());
+        Ok(())
+    }
+
+    #[test]
+    fn document_stream() -> Result<()> {
+        let documents = parse_stream("a = 1\ntext = \"\"\"\n---\n\"\"\"\n---\nb = 2\n---\n")?;
+        assert_eq!(documents.len(), 2);
+        assert_eq!(documents[0].get_string("text")?, "---\n");
+        assert_eq!(documents[1], parse("b = 2\n")?);
+
+        assert_eq!(parse("a = 1\n---\nb = 2\n")?, parse("a = 1\n")?);
+        assert_eq!(parse_stream("")?.len(), 1);
+        // Inside braces `---` isn't a separator, so the object stays unclosed.
+        assert!(parse_stream("a = {\nb = 1\n---\n}\n").is_err());
+        Ok(())
+    }
+
+    #[test]
+    fn source_spans() -> Result<()> {
+        use crate::{lexer::Tokenizer, parser::Parser};
+
+        let content =
+            "name = \"app\"  # kept\nports = [80,  443]\nlimits = {\n  cpu = 1.5\n}\nflag\n";
+        let tokens = Tokenizer::new(content).tokenize()?;
+        let object = Parser::new(tokens)
+            .record_spans(true)
+            .flag_fields(true)
+            .parse()?;
+
+        let text = |object: &CoolDataObject, key| {
+            let (start, end) = object.source_span(key).unwrap();
+            &content[start..end]
+        };
+        assert_eq!(text(&object, "name"), "\"app\"");
+        assert_eq!(text(&object, "ports"), "[80,  443]");
+        assert_eq!(text(&object, "limits"), "{\n  cpu = 1.5\n}");
+        assert_eq!(text(object.get_object("limits")?, "cpu"), "1.5");
+        assert_eq!(object.source_span("flag"), None);
+
+        assert_eq!(
+            parse(content.replace("flag\n", ""))?.source_span("name"),
+            None
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn field_error_variants() -> Result<()> {
+        let object = parse("port = \"80\"\n")?;
+
+        let err = object.get_int("host").unwrap_err();
+        assert!(matches!(
+            CoolError::of(&err),
+            Some(CoolError::FieldMissing { name }) if name == "host"
+        ));
+        assert_eq!(err.to_string(), "Unknown field \"host\"");
+
+        let err = object.get_int("port").unwrap_err();
+        assert_eq!(
+            CoolError::of(&err),
+            Some(&CoolError::TypeMismatch {
+                name: "port".to_string(),
+                expected: "i64",
+                found: "string",
+            })
+        );
+        assert_eq!(err.to_string(), "Field \"port\" is not a i64.");
+        assert_eq!(err.kind(), std::io::ErrorKind::InvalidInput);
+
+        assert_eq!(
+            CoolError::of(&parse("x = ?").unwrap_err()),
+            Some(&CoolError::Parse {
+                kind: std::io::ErrorKind::InvalidInput,
+                message: "Unexpected character '?' at 1:5".to_string(),
+                loc: crate::lexer::Loc::new(1, 5),
+            })
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn field_docs() -> Result<()> {
+        use crate::{lexer::Tokenizer, parser::Parser};
+
+        let content = "# Port to listen on.\n#  Must be free.\nport = 80 # trailing\n\n# Orphaned.\n\nname = \"x\"\nlimits = {\n  # In cores.\n  cpu = 2\n}\n";
+        let tokens = Tokenizer::new(content).tokenize()?;
+        let object = Parser::new(tokens).preserve_docs(true).parse()?;
+
+        assert_eq!(
+            object.field_doc("port"),
+            Some("Port to listen on.\nMust be free.")
+        );
+        assert_eq!(object.field_doc("name"), None);
+        assert_eq!(object.field_doc("limits"), None);
+        assert_eq!(
+            object.get_object("limits")?.field_doc("cpu"),
+            Some("In cores.")
+        );
+        assert!(object
+            .to_string()
+            .starts_with("# Port to listen on.\n# Must be free.\nport = 80\n"));
+        assert_eq!(parse(content)?.field_doc("port"), None);
+        Ok(())
+    }
+
+    #[test]
+    fn no_trailing_newline() -> Result<()> {
+        for content in [
+            "x = 1",
+            "x = -1.5",
+            "x = \"text\"",
+            "x = 'c'",
+            "x = null",
+            "x = 1 # comment",
+            "x = [1, 2]",
+            "x = [\n1,\n2\n]",
+            "x = {}",
+            "a = 1\nx = { b = 1 }",
+            "x = {\n  b = 1\n  c = { d = [true] }\n}",
+            "x = {\n  b = 1}",
+        ] {
+            assert_eq!(
+                parse(content)?,
+                parse(format!("{}\n", content))?,
+                "{:?}",
+                content
+            );
+        }
+
+        assert_eq!(parse_value("[1, 2]")?, parse_value("[1, 2]\n")?);
+        assert!(parse("x = {\n  b = 1").is_err());
+        assert!(parse("x = [1, 2").is_err());
+        assert!(parse("x =").is_err());
+        let err = parse("x = {\n  b = 1\n  y").unwrap_err();
+        assert_eq!(err.kind(), std::io::ErrorKind::UnexpectedEof);
+        assert_eq!(
+            err.to_string(),
+            "Expected `=` or `:` after field \"y\" at 3:4, found end of input"
+        );
+
+        let tokens = crate::lexer::Tokenizer::new("x = 1\nverbose").tokenize()?;
+        let object = crate::parser::Parser::new(tokens)
+            .flag_fields(true)
+            .parse()?;
+        assert_eq!(object.get_bool("verbose")?, &true);
+        Ok(())
+    }
+
+    #[test]
+    fn default_for_type_names() {
+        let defaults = [
+            ("int", CoolDataType::Int(0)),
+            ("uint", CoolDataType::UInt(0)),
+            ("float", CoolDataType::Float(0.0)),
+            ("string", CoolDataType::String(String::new())),
+            ("object", CoolDataType::Object(CoolDataObject::new())),
+            ("list", CoolDataType::List(CoolDataList::new())),
+            ("bool", CoolDataType::Bool(false)),
+            ("char", CoolDataType::Char('\0')),
+            ("null", CoolDataType::Null),
+        ];
+        for (name, value) in defaults {
+            assert_eq!(CoolDataType::default_for(name), Some(value.clone()));
+            assert_eq!(value.type_name(), name);
+        }
+
+        #[cfg(feature = "bigint")]
+        assert_eq!(
+            CoolDataType::default_for("bigint"),
+            Some(CoolDataType::BigInt(0.into()))
+        );
+        #[cfg(feature = "base64")]
+        assert_eq!(
+            CoolDataType::default_for("bytes"),
+            Some(CoolDataType::Bytes(Vec::new()))
+        );
+        assert_eq!(CoolDataType::default_for("Int"), None);
+        assert_eq!(CoolDataType::default_for("map"), None);
+    }
+
+    #[test]
+    fn exponents() -> Result<()> {
+        let object = parse("a = 1e3\nb = 1e+3\nc = -2.5E-2\nd = 1E0\n")?;
+        assert_eq!(object.get_float("a")?, &1000.0);
+        assert_eq!(object.get_float("b")?, &1000.0);
+        assert_eq!(object.get_float("c")?, &-0.025);
+        assert_eq!(object.get_float("d")?, &1.0);
+
+        for (content, message) in [
+            ("x = 1e\n", "Expected a digit in the exponent at 1:7"),
+            ("x = 1efoo\n", "Expected a digit in the exponent at 1:7"),
+            ("x = 1e+\n", "Expected a digit in the exponent at 1:8"),
+            ("x = 1.5e-x\n", "Expected a digit in the exponent at 1:10"),
+        ] {
+            assert_eq!(parse(content).unwrap_err().to_string(), message);
+        }
+        Ok(())
+    }
+
+    #[test]
+    fn contains_and_remove_paths() -> Result<()> {
+        let mut object = parse(
+            "server = {\n  tls = { cert = \"a.pem\"\n  key = \"a.key\" }\n  ports = [80, 443, 8080]\n}\nempty = null\n",
+        )?;
+
+        assert!(object.contains_path("server.tls.cert"));
+        assert!(object.contains_path("server.ports.2"));
+        assert!(object.contains_path("empty"));
+        assert!(!object.contains_path("server.ports.3"));
+        assert!(!object.contains_path("server.ports.x"));
+        assert!(!object.contains_path("server.tls.cert.name"));
+        assert!(!object.contains_path("client"));
+
+        assert_eq!(
+            object.remove_path("server.tls.cert"),
+            Some(CoolDataType::String("a.pem".to_string()))
+        );
+        assert!(!object.contains_path("server.tls.cert"));
+        assert_eq!(object.get_path("server.tls.key")?, &"a.key".into());
+
+        assert_eq!(object.remove_path("server.ports.0"), Some(80.into()));
+        assert_eq!(object.get_path("server.ports")?.to_string(), "[443, 8080]");
+
+        assert_eq!(object.remove_path("server.tls.key"), Some("a.key".into()));
+        assert!(object.get_path("server.tls")?.is_object());
+
+        assert_eq!(object.remove_path("empty"), Some(CoolDataType::Null));
+        assert_eq!(object.remove_path("empty"), None);
+        assert_eq!(object.remove_path("server.ports.5"), None);
+        assert_eq!(object.remove_path("server.nothing.x"), None);
+        assert_eq!(
+            object
+                .iter()
+                .map(|(key, _)| key.as_str())
+                .collect::<Vec<_>>(),
+            ["server"]
+        );
+        Ok(())
+    }
 }
@@ -0,0 +1,83 @@
+/// Builds a [`CoolDataObject`](crate::parser::CoolDataObject) inline.
+///
+/// Keys are identifiers or string literals. Values are nested objects in `{}`,
+/// lists in `[]`, or any expression with a `From` conversion into
+/// [`CoolDataType`](crate::parser::CoolDataType).
+///
+/// ```
+/// use cool::prelude::*;
+///
+/// let object = cool! {
+///     name = "x",
+///     count = 3,
+///     ratio = -0.5,
+///     nested = { a = 1 },
+///     tags = ["a", "b"],
+///     "display name" = "X",
+/// };
+///
+/// let parsed = parse(
+///     "name = \"x\"\ncount = 3\nratio = -0.5\nnested = { a = 1 }\ntags = [\"a\", \"b\"]\n\"display name\" = \"X\"\n",
+/// )?;
+/// assert_eq!(object, parsed);
+/// # Ok::<(), std::io::Error>(())
+/// ```
+#[macro_export]
+macro_rules! cool {
+    (@key $key:ident) => {
+        stringify!($key).to_string()
+    };
+    (@key $key:literal) => {
+        $key.to_string()
+    };
+
+    (@fields $object:ident) => {};
+    (@fields $object:ident $key:tt = { $($inner:tt)* } $(, $($rest:tt)*)?) => {
+        $object.add_field(
+            $crate::cool!(@key $key),
+            $crate::parser::CoolDataType::Object($crate::cool! { $($inner)* }),
+        );
+        $crate::cool!(@fields $object $($($rest)*)?);
+    };
+    (@fields $object:ident $key:tt = [ $($elems:tt)* ] $(, $($rest:tt)*)?) => {
+        $object.add_field(
+            $crate::cool!(@key $key),
+            $crate::parser::CoolDataType::List($crate::cool!(@list [ $($elems)* ])),
+        );
+        $crate::cool!(@fields $object $($($rest)*)?);
+    };
+    (@fields $object:ident $key:tt = $value:expr $(, $($rest:tt)*)?) => {
+        $object.add_field(
+            $crate::cool!(@key $key),
+            $crate::parser::CoolDataType::from($value),
+        );
+        $crate::cool!(@fields $object $($($rest)*)?);
+    };
+
+    (@list [ $($elems:tt)* ]) => {{
+        #[allow(unused_mut)]
+        let mut list = $crate::parser::CoolDataList::new();
+        $crate::cool!(@elems list $($elems)*);
+        list
+    }};
+    (@elems $list:ident) => {};
+    (@elems $list:ident { $($inner:tt)* } $(, $($rest:tt)*)?) => {
+        $list.push_object($crate::cool! { $($inner)* });
+        $crate::cool!(@elems $list $($($rest)*)?);
+    };
+    (@elems $list:ident [ $($inner:tt)* ] $(, $($rest:tt)*)?) => {
+        $list.push_list($crate::cool!(@list [ $($inner)* ]));
+        $crate::cool!(@elems $list $($($rest)*)?);
+    };
+    (@elems $list:ident $value:expr $(, $($rest:tt)*)?) => {
+        $list.push($crate::parser::CoolDataType::from($value));
+        $crate::cool!(@elems $list $($($rest)*)?);
+    };
+
+    ($($body:tt)*) => {{
+        #[allow(unused_mut)]
+        let mut object = $crate::parser::CoolDataObject::new();
+        $crate::cool!(@fields object $($body)*);
+        object
+    }};
+}
@@ -1,125 +1,1058 @@
 use std::{
+    borrow::Cow,
     collections::HashMap,
     fmt::Display,
     io::{Error, ErrorKind, Result},
+    num::{IntErrorKind, ParseIntError},
 };
 
+use crate::error::CoolError;
 use crate::lexer::*;
+use crate::ser::{write_list, write_object, write_value, SerializeOptions};
 
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, PartialEq)]
 pub enum CoolDataType {
-    Int(i32),
+    Int(i64),
+    /// An unsigned integer. Literals with a `u` suffix (`100u`) are always unsigned,
+    /// and so are bare literals above `i64::MAX`; every other integer literal is an
+    /// `Int`. Written back with the `u` suffix.
+    UInt(u64),
+    /// An integer literal outside the range of both `Int` and `UInt`.
+    #[cfg(feature = "bigint")]
+    BigInt(num_bigint::BigInt),
+    /// Written as `inf`, `-inf` and `nan` for the special values. Keep in mind that
+    /// NaN never compares equal to anything, itself included.
     Float(f32),
     String(String),
     Object(CoolDataObject),
     List(CoolDataList),
     Bool(bool),
+    /// A single character, written as `'x'`.
+    Char(char),
+    /// Binary data, written as a base64 `b64"..."` literal.
+    #[cfg(feature = "base64")]
+    Bytes(Vec<u8>),
+    Null,
+}
+
+fn strip_plus(val: &str) -> &str {
+    match val.strip_prefix('+') {
+        Some(digits) if digits.starts_with(|c: char| c.is_ascii_digit()) => digits,
+        _ => val,
+    }
+}
+
+/// Tells a syntactically valid integer that doesn't fit its type apart from
+/// one that isn't an integer at all.
+fn int_error(err: ParseIntError, val: &str) -> Error {
+    let message = match err.kind() {
+        IntErrorKind::PosOverflow | IntErrorKind::NegOverflow => "integer literal too large",
+        _ => "not a valid integer",
+    };
+    Error::new(ErrorKind::InvalidInput, format!("{}: {:?}", message, val))
+}
+
+/// The scalar type a string should be converted to, see
+/// [`CoolDataObject::from_str_pairs_with_hints`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ExpectedType {
+    Int,
+    Float,
+    String,
+    Bool,
 }
 
 impl CoolDataType {
+    /// Parses an int literal, allowing an explicit leading `+`.
     pub fn int(val: &str) -> Result<Self> {
-        Ok(Self::Int(val.parse().map_err(|_| {
-            Error::new(ErrorKind::InvalidInput, "Invalid value for int.")
+        let val = strip_plus(val);
+        Ok(Self::Int(val.parse().map_err(|err| int_error(err, val))?))
+    }
+
+    /// Parses an unsigned int literal without the `u` suffix, allowing an explicit
+    /// leading `+`.
+    pub fn uint(val: &str) -> Result<Self> {
+        let val = strip_plus(val);
+        Ok(Self::UInt(val.parse().map_err(|err| int_error(err, val))?))
+    }
+
+    /// Parses an int literal of any size, allowing an explicit leading `+`.
+    #[cfg(feature = "bigint")]
+    pub fn bigint(val: &str) -> Result<Self> {
+        let val = strip_plus(val);
+        Ok(Self::BigInt(val.parse().map_err(|_| {
+            Error::new(
+                ErrorKind::InvalidInput,
+                format!("not a valid integer: {:?}", val),
+            )
         })?))
     }
 
+    /// Parses an unsuffixed int literal as the first of `Int`, `UInt` and, with the
+    /// `bigint` feature, `BigInt` that can hold it.
+    fn int_literal(val: &str) -> Result<Self> {
+        let value = Self::int(val).or_else(|err| Self::uint(val).map_err(|_| err));
+        #[cfg(feature = "bigint")]
+        let value = value.or_else(|err| Self::bigint(val).map_err(|_| err));
+        value
+    }
+
+    /// Parses a float literal, allowing an explicit leading `+`.
     pub fn float(val: &str) -> Result<Self> {
+        let val = strip_plus(val);
         Ok(Self::Float(val.parse().map_err(|_| {
-            Error::new(ErrorKind::InvalidInput, "Invalid value for float.")
+            Error::new(
+                ErrorKind::InvalidInput,
+                format!("not a valid float: {:?}", val),
+            )
+        })?))
+    }
+
+    pub fn bool(val: &str) -> Result<Self> {
+        Ok(Self::Bool(val.parse().map_err(|_| {
+            Error::new(ErrorKind::InvalidInput, "Invalid value for bool.")
         })?))
     }
+
+    /// Converts `val` to the expected type, or infers one (int, then float, then
+    /// bool, falling back to string) when there is no expectation.
+    pub fn from_str_typed(val: &str, expected: Option<ExpectedType>) -> Result<Self> {
+        match expected {
+            Some(ExpectedType::Int) => Self::int(val),
+            Some(ExpectedType::Float) => Self::float(val),
+            Some(ExpectedType::Bool) => Self::bool(val),
+            Some(ExpectedType::String) => Ok(Self::String(val.to_string())),
+            None => Ok(Self::int(val)
+                .or_else(|_| Self::float(val))
+                .or_else(|_| Self::bool(val))
+                .unwrap_or_else(|_| Self::String(val.to_string()))),
+        }
+    }
 }
 
-impl Display for CoolDataType {
-    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+macro_rules! impl_into {
+    ($func_name:ident, $data_type:ident, $type:ty, $name:literal) => {
+        pub fn $func_name(self) -> Result<$type> {
+            match self {
+                CoolDataType::$data_type(val) => Ok(val),
+                other => Err(Error::new(
+                    ErrorKind::InvalidInput,
+                    format!("Expected {}, got {}.", $name, other.type_name()),
+                )),
+            }
+        }
+    };
+}
+
+impl CoolDataType {
+    impl_into!(into_string, String, String, "string");
+    impl_into!(into_int, Int, i64, "int");
+    impl_into!(into_uint, UInt, u64, "uint");
+    #[cfg(feature = "bigint")]
+    impl_into!(into_bigint, BigInt, num_bigint::BigInt, "bigint");
+    impl_into!(into_float, Float, f32, "float");
+    impl_into!(into_bool, Bool, bool, "bool");
+    impl_into!(into_char, Char, char, "char");
+    #[cfg(feature = "base64")]
+    impl_into!(into_bytes, Bytes, Vec<u8>, "bytes");
+    impl_into!(into_object, Object, CoolDataObject, "object");
+    impl_into!(into_list, List, CoolDataList, "list");
+
+    /// Lowercase name of the variant, e.g. `"int"` or `"list"`, for error messages.
+    pub fn type_name(&self) -> &'static str {
+        match self {
+            CoolDataType::Int(_) => "int",
+            CoolDataType::UInt(_) => "uint",
+            #[cfg(feature = "bigint")]
+            CoolDataType::BigInt(_) => "bigint",
+            CoolDataType::Float(_) => "float",
+            CoolDataType::String(_) => "string",
+            CoolDataType::Object(_) => "object",
+            CoolDataType::List(_) => "list",
+            CoolDataType::Bool(_) => "bool",
+            CoolDataType::Char(_) => "char",
+            #[cfg(feature = "base64")]
+            CoolDataType::Bytes(_) => "bytes",
+            CoolDataType::Null => "null",
+        }
+    }
+
+    /// The zero value of the type named like [`CoolDataType::type_name`] returns,
+    /// such as `Int(0)` for `"int"`, or `None` for an unknown name.
+    pub fn default_for(type_name: &str) -> Option<Self> {
+        Some(match type_name {
+            "int" => CoolDataType::Int(0),
+            "uint" => CoolDataType::UInt(0),
+            #[cfg(feature = "bigint")]
+            "bigint" => CoolDataType::BigInt(Default::default()),
+            "float" => CoolDataType::Float(0.0),
+            "string" => CoolDataType::String(String::new()),
+            "object" => CoolDataType::Object(CoolDataObject::new()),
+            "list" => CoolDataType::List(CoolDataList::new()),
+            "bool" => CoolDataType::Bool(false),
+            "char" => CoolDataType::Char('\0'),
+            #[cfg(feature = "base64")]
+            "bytes" => CoolDataType::Bytes(Vec::new()),
+            "null" => CoolDataType::Null,
+            _ => return None,
+        })
+    }
+
+    pub fn is_int(&self) -> bool {
+        matches!(self, CoolDataType::Int(_))
+    }
+
+    pub fn is_uint(&self) -> bool {
+        matches!(self, CoolDataType::UInt(_))
+    }
+
+    #[cfg(feature = "bigint")]
+    pub fn is_bigint(&self) -> bool {
+        matches!(self, CoolDataType::BigInt(_))
+    }
+
+    pub fn is_float(&self) -> bool {
+        matches!(self, CoolDataType::Float(_))
+    }
+
+    pub fn is_string(&self) -> bool {
+        matches!(self, CoolDataType::String(_))
+    }
+
+    pub fn is_object(&self) -> bool {
+        matches!(self, CoolDataType::Object(_))
+    }
+
+    pub fn is_list(&self) -> bool {
+        matches!(self, CoolDataType::List(_))
+    }
+
+    pub fn is_bool(&self) -> bool {
+        matches!(self, CoolDataType::Bool(_))
+    }
+
+    pub fn is_char(&self) -> bool {
+        matches!(self, CoolDataType::Char(_))
+    }
+
+    pub fn is_null(&self) -> bool {
+        matches!(self, CoolDataType::Null)
+    }
+
+    fn shrink_to_fit(&mut self) {
         match self {
-            CoolDataType::Int(val) => write!(f, "{}", val),
-            CoolDataType::Float(val) => write!(f, "{}", val),
-            CoolDataType::String(val) => write!(f, "{:?}", val),
-            CoolDataType::Object(val) => write!(f, "{{\n{}}}", val),
-            CoolDataType::List(val) => write!(f, "{}", val),
-            CoolDataType::Bool(val) => write!(f, "{}", val),
+            CoolDataType::Object(val) => val.shrink_to_fit(),
+            CoolDataType::List(val) => val.shrink_to_fit(),
+            CoolDataType::String(val) => val.shrink_to_fit(),
+            _ => {}
+        }
+    }
+
+    /// Renders the value in a single-line form that is identical across runs and
+    /// platforms, for use in snapshot tests.
+    ///
+    /// Floats use the shortest representation that parses back to the same value,
+    /// always with a fractional part or exponent (`1.0`, `1e20`), keep the sign of
+    /// zero (`-0.0`), and spell out `nan`, `inf` and `-inf`. Objects render as
+    /// `{ key = value, ... }` in field order and lists as `[value, ...]`.
+    pub fn to_stable_string(&self) -> String {
+        match self {
+            CoolDataType::Float(val) if val.is_nan() => "nan".to_string(),
+            CoolDataType::Float(val) if val.is_infinite() => if val.is_sign_negative() {
+                "-inf"
+            } else {
+                "inf"
+            }
+            .to_string(),
+            CoolDataType::Float(val) => format!("{:?}", val),
+            CoolDataType::String(val) => format!("{:?}", val),
+            CoolDataType::Object(val) => {
+                let fields: Vec<String> = val
+                    .iter()
+                    .map(|(key, value)| format!("{} = {}", Key(key), value.to_stable_string()))
+                    .collect();
+                if fields.is_empty() {
+                    "{}".to_string()
+                } else {
+                    format!("{{ {} }}", fields.join(", "))
+                }
+            }
+            CoolDataType::List(val) => {
+                let values: Vec<String> = val.0.iter().map(Self::to_stable_string).collect();
+                format!("[{}]", values.join(", "))
+            }
+            other => other.to_string(),
+        }
+    }
+
+    fn write_canonical(&self, out: &mut String) {
+        match self {
+            CoolDataType::Float(val) if val.is_nan() => out.push_str("nan"),
+            CoolDataType::Float(val) if *val == 0.0 => out.push_str("0.0"),
+            CoolDataType::Object(val) => val.write_canonical(out),
+            CoolDataType::List(val) => {
+                out.push('[');
+                for (i, value) in val.0.iter().enumerate() {
+                    if i > 0 {
+                        out.push(',');
+                    }
+                    value.write_canonical(out);
+                }
+                out.push(']');
+            }
+            other => out.push_str(&other.to_stable_string()),
+        }
+    }
+
+    fn sorted(&self) -> Self {
+        match self {
+            CoolDataType::Object(val) => CoolDataType::Object(val.sorted()),
+            CoolDataType::List(val) => {
+                CoolDataType::List(CoolDataList(val.0.iter().map(Self::sorted).collect()))
+            }
+            other => other.clone(),
+        }
+    }
+
+    /// The order [`CoolDataList::sort`] uses. Lists compare element-wise.
+    fn total_cmp(&self, other: &Self) -> std::cmp::Ordering {
+        use CoolDataType::*;
+        fn rank(value: &CoolDataType) -> u8 {
+            match value {
+                Null => 0,
+                Bool(_) => 1,
+                Int(_) | UInt(_) | Float(_) => 2,
+                #[cfg(feature = "bigint")]
+                BigInt(_) => 2,
+                Char(_) => 3,
+                String(_) => 4,
+                List(_) => 5,
+                _ => 6,
+            }
+        }
+
+        match (self, other) {
+            (Bool(a), Bool(b)) => a.cmp(b),
+            (Int(a), Int(b)) => a.cmp(b),
+            (UInt(a), UInt(b)) => a.cmp(b),
+            (Int(a), UInt(b)) => (*a as i128).cmp(&(*b as i128)),
+            (UInt(a), Int(b)) => (*a as i128).cmp(&(*b as i128)),
+            (UInt(a), Float(b)) => (*a as f64).total_cmp(&(*b as f64)),
+            #[cfg(feature = "bigint")]
+            (BigInt(a), BigInt(b)) => a.cmp(b),
+            #[cfg(feature = "bigint")]
+            (BigInt(a), Int(b)) => a.cmp(&(*b).into()),
+            #[cfg(feature = "bigint")]
+            (Int(a), BigInt(b)) => num_bigint::BigInt::from(*a).cmp(b),
+            #[cfg(feature = "bigint")]
+            (BigInt(a), UInt(b)) => a.cmp(&(*b).into()),
+            #[cfg(feature = "bigint")]
+            (UInt(a), BigInt(b)) => num_bigint::BigInt::from(*a).cmp(b),
+            (Float(a), UInt(b)) => (*a as f64).total_cmp(&(*b as f64)),
+            (Int(a), Float(b)) => (*a as f64).total_cmp(&(*b as f64)),
+            (Float(a), Int(b)) => (*a as f64).total_cmp(&(*b as f64)),
+            (Float(a), Float(b)) => a.total_cmp(b),
+            (Char(a), Char(b)) => a.cmp(b),
+            (String(a), String(b)) => a.cmp(b),
+            (List(a), List(b)) => {
+                a.0.iter()
+                    .zip(b.0.iter())
+                    .map(|(a, b)| a.total_cmp(b))
+                    .find(|ordering| ordering.is_ne())
+                    .unwrap_or(a.0.len().cmp(&b.0.len()))
+            }
+            _ => rank(self).cmp(&rank(other)),
+        }
+    }
+
+    fn compact(&mut self, drop_null: bool, drop_empty_containers: bool) {
+        match self {
+            CoolDataType::Object(val) => val.compact(drop_null, drop_empty_containers),
+            CoolDataType::List(val) => {
+                for value in val.0.iter_mut() {
+                    value.compact(drop_null, drop_empty_containers);
+                }
+            }
+            _ => {}
+        }
+    }
+
+    fn is_prunable(&self, drop_null: bool, drop_empty_containers: bool) -> bool {
+        match self {
+            CoolDataType::Null => drop_null,
+            CoolDataType::Object(val) => drop_empty_containers && val.fields.is_empty(),
+            CoolDataType::List(val) => drop_empty_containers && val.0.is_empty(),
+            _ => false,
         }
     }
 }
 
+macro_rules! impl_from {
+    ($data_type:ident, $type:ty) => {
+        impl From<$type> for CoolDataType {
+            fn from(value: $type) -> Self {
+                CoolDataType::$data_type(value.into())
+            }
+        }
+    };
+}
+
+impl_from!(Int, i64);
+impl_from!(Int, i32);
+impl_from!(UInt, u64);
+#[cfg(feature = "bigint")]
+impl_from!(BigInt, num_bigint::BigInt);
+impl_from!(Float, f32);
+impl_from!(Bool, bool);
+impl_from!(Char, char);
+impl_from!(String, String);
+impl_from!(String, &str);
+impl_from!(Object, CoolDataObject);
+impl_from!(List, CoolDataList);
+
+impl<T: Into<CoolDataType>> From<Vec<T>> for CoolDataType {
+    fn from(value: Vec<T>) -> Self {
+        CoolDataType::List(CoolDataList(value.into_iter().map(Into::into).collect()))
+    }
+}
+
+impl Display for CoolDataType {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write_value(f, self, &SerializeOptions::default())
+    }
+}
+
+/// What an object knows about a field besides its value.
+#[derive(Debug, Clone, Default)]
+struct FieldNotes {
+    source: Option<String>,
+    comment: Option<String>,
+    doc: Option<String>,
+    span: Option<(usize, usize)>,
+}
+
+/// An object whose fields keep the order they were added in.
 #[derive(Debug, Clone)]
-pub struct CoolDataObject(pub HashMap<String, CoolDataType>);
+pub struct CoolDataObject {
+    fields: HashMap<String, CoolDataType>,
+    order: Vec<String>,
+    notes: HashMap<String, FieldNotes>,
+    folded: Option<HashMap<String, String>>,
+}
+
+fn type_mismatch(name: &str, expected: &'static str, found: &CoolDataType) -> Error {
+    CoolError::TypeMismatch {
+        name: name.to_string(),
+        expected,
+        found: found.type_name(),
+    }
+    .into()
+}
+
+fn field_missing(name: &str) -> Error {
+    CoolError::FieldMissing {
+        name: name.to_string(),
+    }
+    .into()
+}
 
 macro_rules! impl_get {
     ($func_name:ident, $func_mut_name:ident, $data_type:ident, $type:ty) => {
         pub fn $func_name(&self, name: &str) -> Result<&$type> {
-            let CoolDataType::$data_type(val) = self.get_field(name)? else {
-                return Err(Error::new(
-                    ErrorKind::InvalidInput,
-                    format!("Field {:?} is not a {}.", name, stringify!($type)),
-                ));
+            let field = self.get_field(name)?;
+            let CoolDataType::$data_type(val) = field else {
+                return Err(type_mismatch(name, stringify!($type), field));
             };
             Ok(val)
         }
 
         pub fn $func_mut_name(&mut self, name: &str) -> Result<&mut $type> {
-            let CoolDataType::$data_type(val) = self.get_field_mut(name)? else {
-                return Err(Error::new(
-                    ErrorKind::InvalidInput,
-                    format!("Field {:?} is not a {}.", name, stringify!($type)),
-                ));
+            let field = self.get_field_mut(name)?;
+            let CoolDataType::$data_type(val) = field else {
+                return Err(type_mismatch(name, stringify!($type), field));
             };
             Ok(val)
         }
     };
 }
 
+macro_rules! impl_get_or {
+    ($func_name:ident, $getter:ident, $type:ty) => {
+        /// Returns `default` if the field is absent, but still errors if it's
+        /// present with another type, `null` included.
+        pub fn $func_name(&self, name: &str, default: $type) -> Result<$type> {
+            if self.get_field(name).is_err() {
+                return Ok(default);
+            }
+            self.$getter(name).cloned()
+        }
+    };
+}
+
 impl CoolDataObject {
+    pub fn builder() -> CoolDataObjectBuilder {
+        CoolDataObjectBuilder::new()
+    }
+
     pub fn new() -> Self {
-        Self(HashMap::new())
+        Self {
+            fields: HashMap::new(),
+            order: Vec::new(),
+            notes: HashMap::new(),
+            folded: None,
+        }
     }
 
+    /// Adds a field, or replaces its value in place if it already exists.
     pub fn add_field(&mut self, name: String, value: CoolDataType) {
-        self.0.insert(name, value);
+        if !self.fields.contains_key(&name) {
+            self.order.push(name.clone());
+            if let Some(folded) = &mut self.folded {
+                folded.entry(name.to_lowercase()).or_insert(name.clone());
+            }
+        }
+        self.fields.insert(name, value);
+    }
+
+    /// Renames the field `from` to `to`, keeping its value, position, comment and
+    /// source. Both names are looked up like `get_field`, so on a case-insensitive
+    /// object a rename may change only the key's case. Errors with
+    /// [`CoolError::FieldMissing`] if `from` is absent, and with `InvalidInput` if
+    /// `to` names another field.
+    pub fn rename_field(&mut self, from: &str, to: &str) -> Result<()> {
+        let key = self.resolve_key(from);
+        if !self.fields.contains_key(key) {
+            return Err(field_missing(from));
+        }
+        let key = key.to_string();
+        let existing = self.resolve_key(to);
+        if existing != key && self.fields.contains_key(existing) {
+            return Err(Error::new(
+                ErrorKind::InvalidInput,
+                format!(
+                    "Can't rename {:?} to {:?}: field {:?} exists.",
+                    from, to, existing
+                ),
+            ));
+        }
+
+        let value = self.fields.remove(&key).unwrap();
+        self.fields.insert(to.to_string(), value);
+        if let Some(slot) = self.order.iter_mut().find(|slot| **slot == key) {
+            *slot = to.to_string();
+        }
+        if let Some(notes) = self.notes.remove(&key) {
+            self.notes.insert(to.to_string(), notes);
+        }
+        self.set_case_insensitive(self.folded.is_some());
+        Ok(())
+    }
+
+    /// Makes `get_field` fall back to a case-insensitive match when there is no
+    /// exact one. Keys keep their original spelling; if several keys differ only
+    /// by case, the first one added wins the fallback.
+    pub fn set_case_insensitive(&mut self, enabled: bool) {
+        self.folded = enabled.then(|| {
+            let mut folded = HashMap::new();
+            for key in self.order.iter() {
+                folded.entry(key.to_lowercase()).or_insert(key.clone());
+            }
+            folded
+        });
+    }
+
+    fn resolve_key<'a>(&'a self, name: &'a str) -> &'a str {
+        if self.fields.contains_key(name) {
+            return name;
+        }
+        self.folded
+            .as_ref()
+            .and_then(|folded| folded.get(&name.to_lowercase()))
+            .map_or(name, String::as_str)
+    }
+
+    /// Builds an object from string key/value pairs, converting each value to the
+    /// type hinted for its key or inferring one when there is no hint.
+    pub fn from_str_pairs_with_hints(
+        pairs: &[(&str, &str)],
+        hints: &HashMap<&str, ExpectedType>,
+    ) -> Result<Self> {
+        let mut out = Self::new();
+        for (key, val) in pairs {
+            let value =
+                CoolDataType::from_str_typed(val, hints.get(key).copied()).map_err(|err| {
+                    Error::new(
+                        err.kind(),
+                        format!("Field {:?}: {} Got {:?}.", key, err, val),
+                    )
+                })?;
+            out.add_field(key.to_string(), value);
+        }
+        Ok(out)
+    }
+
+    /// Moves every field of `other` into `self`. For keys present in both, `resolve`
+    /// receives the key, the current value and the incoming value and returns the
+    /// value to keep.
+    pub fn merge_with(
+        &mut self,
+        other: CoolDataObject,
+        resolve: impl Fn(&str, &CoolDataType, &CoolDataType) -> CoolDataType,
+    ) {
+        for (key, value) in other {
+            match self.fields.get_mut(&key) {
+                Some(current) => *current = resolve(&key, current, &value),
+                None => self.add_field(key, value),
+            }
+        }
+    }
+
+    /// Renders the top-level scalar fields as a URL query string such as
+    /// `name=my%20app&port=8080`, percent-encoding keys and values. Strings are
+    /// written without quotes. Nested objects, lists and `null` fields have no flat
+    /// representation and are skipped.
+    pub fn to_query_string(&self) -> String {
+        let mut pairs = Vec::new();
+        for (key, value) in self.iter() {
+            let value = match value {
+                CoolDataType::String(val) => val.clone(),
+                CoolDataType::Char(val) => val.to_string(),
+                CoolDataType::Int(_)
+                | CoolDataType::UInt(_)
+                | CoolDataType::Float(_)
+                | CoolDataType::Bool(_) => value.to_string(),
+                #[cfg(feature = "bigint")]
+                CoolDataType::BigInt(_) => value.to_string(),
+                CoolDataType::Object(_) | CoolDataType::List(_) | CoolDataType::Null => continue,
+                #[cfg(feature = "base64")]
+                CoolDataType::Bytes(_) => continue,
+            };
+            pairs.push(format!(
+                "{}={}",
+                percent_encode(key),
+                percent_encode(&value)
+            ));
+        }
+        pairs.join("&")
+    }
+
+    /// Moves every field of `other` into `self`, overriding existing values, and
+    /// records `source_name` as the origin of each of those fields.
+    pub fn merge_tracked(&mut self, other: CoolDataObject, source_name: &str) {
+        for (key, value) in other {
+            self.notes.entry(key.clone()).or_default().source = Some(source_name.to_string());
+            self.add_field(key, value);
+        }
+    }
+
+    /// The source the field was last merged from by [`CoolDataObject::merge_tracked`].
+    pub fn field_source(&self, key: &str) -> Option<&str> {
+        self.notes.get(key)?.source.as_deref()
+    }
+
+    /// The `#` comment trailing the field's value, kept when parsing with
+    /// [`Parser::preserve_comments`] and written back on serialization.
+    pub fn field_comment(&self, key: &str) -> Option<&str> {
+        self.notes.get(key)?.comment.as_deref()
+    }
+
+    pub fn set_field_comment(&mut self, key: String, comment: impl Into<String>) {
+        self.notes.entry(key).or_default().comment = Some(comment.into());
+    }
+
+    /// The `#` comment lines right above the field, joined with newlines, kept when
+    /// parsing with [`Parser::preserve_docs`] and written back on serialization.
+    pub fn field_doc(&self, key: &str) -> Option<&str> {
+        self.notes.get(key)?.doc.as_deref()
+    }
+
+    pub fn set_field_doc(&mut self, key: String, doc: impl Into<String>) {
+        self.notes.entry(key).or_default().doc = Some(doc.into());
+    }
+
+    /// Start and end byte offsets of the field's value in the parsed text, kept
+    /// when parsing with [`Parser::record_spans`]. Slicing the source with them
+    /// gives the value exactly as written.
+    pub fn source_span(&self, key: &str) -> Option<(usize, usize)> {
+        self.notes.get(key)?.span
+    }
+
+    /// Gets the field's entry for in-place insert-or-update, like `HashMap::entry`.
+    pub fn entry(&mut self, key: String) -> Entry<'_> {
+        Entry { object: self, key }
+    }
+
+    /// Returns the fields as a plain map, dropping their order.
+    pub fn into_inner(self) -> HashMap<String, CoolDataType> {
+        self.fields
+    }
+
+    /// Read-only view of the fields as a map. The map doesn't keep insertion
+    /// order; use [`CoolDataObject::iter`] for that.
+    pub fn as_map(&self) -> &HashMap<String, CoolDataType> {
+        &self.fields
+    }
+
+    /// Number of fields the object can hold without reallocating.
+    pub fn capacity(&self) -> usize {
+        self.fields.capacity().min(self.order.capacity())
+    }
+
+    /// Releases excess capacity here and in every nested object and list.
+    pub fn shrink_to_fit(&mut self) {
+        self.fields.shrink_to_fit();
+        self.order.shrink_to_fit();
+        for value in self.fields.values_mut() {
+            value.shrink_to_fit();
+        }
+    }
+
+    /// Recursively removes `null` fields and/or fields holding empty objects or
+    /// lists. Nested objects are compacted first, so one left empty by that is
+    /// dropped too. List elements themselves are never removed.
+    pub fn compact(&mut self, drop_null: bool, drop_empty_containers: bool) {
+        for value in self.fields.values_mut() {
+            value.compact(drop_null, drop_empty_containers);
+        }
+        self.retain_fields(|_, value| !value.is_prunable(drop_null, drop_empty_containers));
+    }
+
+    fn retain_fields(&mut self, mut f: impl FnMut(&str, &CoolDataType) -> bool) {
+        self.fields.retain(|key, value| f(key, value));
+        let fields = &self.fields;
+        self.order.retain(|key| fields.contains_key(key));
+        self.notes.retain(|key, _| fields.contains_key(key));
+        self.set_case_insensitive(self.folded.is_some());
+    }
+
+    /// Iterates over the fields in insertion order.
+    pub fn iter(&self) -> Iter<'_> {
+        Iter {
+            fields: &self.fields,
+            keys: self.order.iter(),
+        }
+    }
+
+    /// Returns a copy with keys sorted lexicographically, recursing into nested
+    /// values. Field comments, docs, sources and case-insensitivity carry over.
+    pub fn sorted(&self) -> Self {
+        let mut keys: Vec<&String> = self.order.iter().collect();
+        keys.sort();
+
+        let mut out = Self {
+            notes: self.notes.clone(),
+            ..Self::new()
+        };
+        for key in keys {
+            out.add_field(key.clone(), self.fields[key].sorted());
+        }
+        out.set_case_insensitive(self.folded.is_some());
+        out
+    }
+
+    /// Serializes the object into bytes that are equal exactly when the objects
+    /// are equal, for hashing and caching. The rules are:
+    ///
+    /// - Keys are sorted by their UTF-8 bytes at every level, and field comments,
+    ///   sources and case-insensitivity are left out.
+    /// - Objects are written as `{key=value,...}` and lists as `[value,...]`, with
+    ///   no whitespace. Keys are quoted only where the `Display` impl would.
+    /// - Floats use the shortest form that parses back to the same value (`1.0`,
+    ///   `1e20`), `-0.0` is written as `0.0` and every NaN as `nan`. Ints and
+    ///   floats stay distinct, so `1` and `1.0` differ.
+    /// - Quoted keys are written like in `Display`: between `"`, with `"`, `\`,
+    ///   newline, carriage return, tab and NUL escaped as `\"`, `\\`, `\n`, `\r`,
+    ///   `\t` and `\0`, and every other character as is.
+    /// - String values are written with Rust's `Debug` for `str`, which on top of
+    ///   those escapes writes other control characters and non-printable
+    ///   Unicode as `\u{...}`.
+    /// - Chars are written like in `Display`: between `'`, with the same escapes
+    ///   as quoted keys except that `'` is escaped as `\'` instead of `"`.
+    /// - Every other value is written as in `Display`, such as `100u` for a `UInt`.
+    pub fn to_canonical_bytes(&self) -> Vec<u8> {
+        let mut out = String::new();
+        self.write_canonical(&mut out);
+        out.into_bytes()
+    }
+
+    fn write_canonical(&self, out: &mut String) {
+        let mut keys: Vec<&String> = self.order.iter().collect();
+        keys.sort();
+
+        out.push('{');
+        for (i, key) in keys.into_iter().enumerate() {
+            if i > 0 {
+                out.push(',');
+            }
+            out.push_str(&Key(key).to_string());
+            out.push('=');
+            self.fields[key].write_canonical(out);
+        }
+        out.push('}');
     }
 
     pub fn get_field(&self, name: &str) -> Result<&CoolDataType> {
-        self.0.get(name).ok_or(Error::new(
-            ErrorKind::InvalidInput,
-            format!("Unknown field {:?}", name),
-        ))
+        self.try_field(name).ok_or_else(|| field_missing(name))
     }
 
-    pub fn get_field_mut(&mut self, name: &str) -> Result<&mut CoolDataType> {
-        self.0.get_mut(name).ok_or(Error::new(
-            ErrorKind::InvalidInput,
-            format!("Unknown field {:?}", name),
-        ))
+    /// Looks up a field ignoring case, erroring if no key or more than one key
+    /// matches. Unlike [`CoolDataObject::get_field`] this scans every key, so it's O(n).
+    pub fn get_field_ci(&self, name: &str) -> Result<&CoolDataType> {
+        let folded = name.to_lowercase();
+        let mut matches = self.order.iter().filter(|key| key.to_lowercase() == folded);
+
+        match (matches.next(), matches.next()) {
+            (Some(key), None) => Ok(&self.fields[key]),
+            (None, _) => Err(field_missing(name)),
+            (Some(first), Some(second)) => Err(Error::new(
+                ErrorKind::InvalidInput,
+                format!(
+                    "Field {:?} is ambiguous, it matches both {:?} and {:?}",
+                    name, first, second
+                ),
+            )),
+        }
+    }
+
+    /// Like [`CoolDataObject::get_field`], but returns `None` for a missing field
+    /// instead of building an error.
+    pub fn try_field(&self, name: &str) -> Option<&CoolDataType> {
+        self.fields.get(self.resolve_key(name))
+    }
+
+    pub fn get_field_mut(&mut self, name: &str) -> Result<&mut CoolDataType> {
+        let key = self.resolve_key(name).to_string();
+        self.fields.get_mut(&key).ok_or_else(|| field_missing(name))
+    }
+
+    /// Removes the field along with its comment and other notes, returning its value.
+    pub fn remove_field(&mut self, name: &str) -> Option<CoolDataType> {
+        let key = self.resolve_key(name).to_string();
+        let value = self.fields.remove(&key)?;
+        self.order.retain(|existing| *existing != key);
+        self.notes.remove(&key);
+        self.set_case_insensitive(self.folded.is_some());
+        Some(value)
+    }
+
+    impl_get!(get_string, get_string_mut, String, String);
+    impl_get!(get_int, get_int_mut, Int, i64);
+    impl_get!(get_uint, get_uint_mut, UInt, u64);
+    #[cfg(feature = "bigint")]
+    impl_get!(get_bigint, get_bigint_mut, BigInt, num_bigint::BigInt);
+    impl_get!(get_float, get_float_mut, Float, f32);
+    impl_get!(get_bool, get_bool_mut, Bool, bool);
+    impl_get!(get_char, get_char_mut, Char, char);
+    #[cfg(feature = "base64")]
+    impl_get!(get_bytes, get_bytes_mut, Bytes, Vec<u8>);
+    impl_get!(get_object, get_object_mut, Object, CoolDataObject);
+    impl_get!(get_list, get_list_mut, List, CoolDataList);
+
+    impl_get_or!(get_string_or, get_string, String);
+    impl_get_or!(get_int_or, get_int, i64);
+    impl_get_or!(get_uint_or, get_uint, u64);
+    impl_get_or!(get_float_or, get_float, f32);
+    impl_get_or!(get_bool_or, get_bool, bool);
+    impl_get_or!(get_char_or, get_char, char);
+
+    /// Like [`CoolDataObject::get_int`], but also accepts a float with no
+    /// fractional part, such as `3.0`, and a uint that fits in an `i64`.
+    pub fn get_int_lenient(&self, name: &str) -> Result<i64> {
+        let not_integral = || {
+            Error::new(
+                ErrorKind::InvalidInput,
+                format!("Field {:?} is not an integer.", name),
+            )
+        };
+        match self.get_field(name)? {
+            CoolDataType::Int(val) => Ok(*val),
+            CoolDataType::UInt(val) => i64::try_from(*val).map_err(|_| not_integral()),
+            CoolDataType::Float(val)
+                if val.fract() == 0.0 && (i64::MIN as f32..i64::MAX as f32).contains(val) =>
+            {
+                Ok(*val as i64)
+            }
+            _ => Err(not_integral()),
+        }
+    }
+}
+
+/// Fields take the map's iteration order, which is arbitrary.
+impl From<HashMap<String, CoolDataType>> for CoolDataObject {
+    fn from(fields: HashMap<String, CoolDataType>) -> Self {
+        let order = fields.keys().cloned().collect();
+        Self {
+            fields,
+            order,
+            ..Self::new()
+        }
+    }
+}
+
+/// Objects are equal when they have the same fields with equal values, in any order.
+impl PartialEq for CoolDataObject {
+    fn eq(&self, other: &Self) -> bool {
+        self.fields == other.fields
+    }
+}
+
+impl Default for CoolDataObject {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+fn percent_encode(text: &str) -> String {
+    let mut out = String::new();
+    for byte in text.bytes() {
+        if byte.is_ascii_alphanumeric() || matches!(byte, b'-' | b'.' | b'_' | b'~') {
+            out.push(byte as char);
+        } else {
+            out.push_str(&format!("%{:02X}", byte));
+        }
+    }
+    out
+}
+
+macro_rules! impl_build {
+    ($func_name:ident, $data_type:ident, $type:ty) => {
+        pub fn $func_name(self, name: impl Into<String>, value: $type) -> Self {
+            self.field(name, CoolDataType::$data_type(value.into()))
+        }
+    };
+}
+
+/// Assembles a [`CoolDataObject`] field by field, see [`CoolDataObject::builder`].
+///
+/// ```
+/// use cool::prelude::*;
+///
+/// let limits = CoolDataObject::builder().float("cpu", 1.5).int("mem", 512).build();
+/// let object = CoolDataObject::builder()
+///     .string("name", "app")
+///     .object("limits", limits)
+///     .list("ports", CoolDataList(vec![80.into(), 443.into()]))
+///     .build();
+///
+/// assert_eq!(object.get_string("name")?, "app");
+/// assert_eq!(object.get_object("limits")?.get_int("mem")?, &512);
+/// # Ok::<(), std::io::Error>(())
+/// ```
+#[derive(Debug, Clone, Default)]
+pub struct CoolDataObjectBuilder {
+    object: CoolDataObject,
+}
+
+impl CoolDataObjectBuilder {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Adds a field, replacing the value of an earlier one with the same name.
+    pub fn field(mut self, name: impl Into<String>, value: CoolDataType) -> Self {
+        self.object.add_field(name.into(), value);
+        self
+    }
+
+    impl_build!(int, Int, i64);
+    impl_build!(float, Float, f32);
+    impl_build!(string, String, impl Into<String>);
+    impl_build!(bool, Bool, bool);
+    impl_build!(object, Object, CoolDataObject);
+    impl_build!(list, List, CoolDataList);
+
+    pub fn build(self) -> CoolDataObject {
+        self.object
+    }
+}
+
+pub struct Entry<'a> {
+    object: &'a mut CoolDataObject,
+    key: String,
+}
+
+impl<'a> Entry<'a> {
+    pub fn key(&self) -> &str {
+        &self.key
+    }
+
+    pub fn or_insert(self, default: CoolDataType) -> &'a mut CoolDataType {
+        self.or_insert_with(|| default)
+    }
+
+    pub fn or_insert_with(self, default: impl FnOnce() -> CoolDataType) -> &'a mut CoolDataType {
+        if !self.object.fields.contains_key(&self.key) {
+            self.object.add_field(self.key.clone(), default());
+        }
+        self.object.fields.get_mut(&self.key).unwrap()
+    }
+}
+
+pub struct Iter<'a> {
+    fields: &'a HashMap<String, CoolDataType>,
+    keys: std::slice::Iter<'a, String>,
+}
+
+impl<'a> Iterator for Iter<'a> {
+    type Item = (&'a String, &'a CoolDataType);
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let key = self.keys.next()?;
+        Some((key, &self.fields[key]))
+    }
+}
+
+pub struct IntoIter {
+    fields: HashMap<String, CoolDataType>,
+    keys: std::vec::IntoIter<String>,
+}
+
+impl Iterator for IntoIter {
+    type Item = (String, CoolDataType);
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let key = self.keys.next()?;
+        let value = self.fields.remove(&key)?;
+        Some((key, value))
     }
-
-    impl_get!(get_string, get_string_mut, String, String);
-    impl_get!(get_int, get_int_mut, Int, i32);
-    impl_get!(get_float, get_float_mut, Float, f32);
-    impl_get!(get_bool, get_bool_mut, Bool, bool);
-    impl_get!(get_object, get_object_mut, Object, CoolDataObject);
-    impl_get!(get_list, get_list_mut, List, CoolDataList);
 }
 
 impl IntoIterator for CoolDataObject {
     type Item = (String, CoolDataType);
-    type IntoIter = std::collections::hash_map::IntoIter<String, CoolDataType>;
+    type IntoIter = IntoIter;
 
     fn into_iter(self) -> Self::IntoIter {
-        self.0.into_iter()
+        IntoIter {
+            fields: self.fields,
+            keys: self.order.into_iter(),
+        }
     }
 }
 
-impl Display for CoolDataObject {
+/// Displays a field name, quoting it unless it lexes as a bare identifier.
+pub(crate) struct Key<'a>(pub &'a str);
+
+impl Display for Key<'_> {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
-        for (key, value) in self.0.iter() {
-            writeln!(f, "{} = {}", key, value)?;
+        let mut chars = self.0.chars();
+        let bare = chars.next().is_some_and(|c| c.is_alphabetic())
+            && chars.all(|c| c.is_alphabetic() || c == '_')
+            && !matches!(self.0, "true" | "false" | "null" | "inf" | "nan");
+        if bare {
+            write!(f, "{}", self.0)
+        } else {
+            crate::ser::write_quoted(f, self.0)
         }
-        Ok(())
     }
 }
 
-#[derive(Debug, Clone)]
+impl Display for CoolDataObject {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write_object(f, self, &SerializeOptions::default())
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
 pub struct CoolDataList(pub Vec<CoolDataType>);
 
 macro_rules! impl_at {
@@ -146,11 +1079,50 @@ macro_rules! impl_at {
     };
 }
 
+macro_rules! impl_first_last {
+    ($first_name:ident, $last_name:ident, $data_type:ident, $type:ty) => {
+        pub fn $first_name(&self) -> Option<&$type> {
+            self.0.iter().find_map(|value| match value {
+                CoolDataType::$data_type(val) => Some(val),
+                _ => None,
+            })
+        }
+
+        pub fn $last_name(&self) -> Option<&$type> {
+            self.0.iter().rev().find_map(|value| match value {
+                CoolDataType::$data_type(val) => Some(val),
+                _ => None,
+            })
+        }
+    };
+}
+
+macro_rules! impl_push {
+    ($func_name:ident, $data_type:ident, $type:ty) => {
+        pub fn $func_name(&mut self, value: $type) -> &mut Self {
+            self.push(CoolDataType::$data_type(value.into()))
+        }
+    };
+}
+
 impl CoolDataList {
     pub fn new() -> Self {
         Self(Vec::new())
     }
 
+    pub fn push(&mut self, value: CoolDataType) -> &mut Self {
+        self.0.push(value);
+        self
+    }
+
+    impl_push!(push_string, String, impl Into<String>);
+    impl_push!(push_int, Int, i64);
+    impl_push!(push_float, Float, f32);
+    impl_push!(push_bool, Bool, bool);
+    impl_push!(push_char, Char, char);
+    impl_push!(push_object, Object, CoolDataObject);
+    impl_push!(push_list, List, CoolDataList);
+
     pub fn at(&self, index: usize) -> Result<&CoolDataType> {
         self.0.get(index).ok_or(Error::new(
             ErrorKind::InvalidInput,
@@ -165,39 +1137,317 @@ impl CoolDataList {
         ))
     }
 
+    pub fn swap(&mut self, a: usize, b: usize) -> Result<()> {
+        for index in [a, b] {
+            if index >= self.0.len() {
+                return Err(Error::new(
+                    ErrorKind::InvalidInput,
+                    format!("Index {} out of bounds.", index),
+                ));
+            }
+        }
+        self.0.swap(a, b);
+        Ok(())
+    }
+
+    /// Inserts `value` at `index`, shifting later elements back. `index` may be
+    /// the length of the list, which appends.
+    pub fn insert(&mut self, index: usize, value: CoolDataType) -> Result<()> {
+        if index > self.0.len() {
+            return Err(Error::new(
+                ErrorKind::InvalidInput,
+                format!("Index {} out of bounds.", index),
+            ));
+        }
+        self.0.insert(index, value);
+        Ok(())
+    }
+
+    /// Removes the element at `index`, replacing it with the last element.
+    /// Doesn't preserve order, but is O(1).
+    pub fn swap_remove(&mut self, index: usize) -> Result<CoolDataType> {
+        if index >= self.0.len() {
+            return Err(Error::new(
+                ErrorKind::InvalidInput,
+                format!("Index {} out of bounds.", index),
+            ));
+        }
+        Ok(self.0.swap_remove(index))
+    }
+
+    /// Sorts the elements in a total order that groups mixed types:
+    /// `null` < bools < numbers < chars < strings < lists < objects. Ints and
+    /// floats compare numerically against each other. The sort is stable.
+    pub fn sort(&mut self) {
+        self.0.sort_by(CoolDataType::total_cmp);
+    }
+
+    /// Removes consecutive equal elements, like `Vec::dedup`. Sort first to remove
+    /// all duplicates.
+    pub fn dedup(&mut self) {
+        self.0.dedup();
+    }
+
+    pub fn contains(&self, value: &CoolDataType) -> bool {
+        self.0.contains(value)
+    }
+
+    /// Keeps only the elements `f` returns `true` for.
+    pub fn retain<F: FnMut(&CoolDataType) -> bool>(&mut self, f: F) {
+        self.0.retain(f);
+    }
+
+    /// Replaces every element with what `f` returns for it.
+    pub fn map_in_place<F: FnMut(CoolDataType) -> CoolDataType>(&mut self, mut f: F) {
+        for value in self.0.iter_mut() {
+            *value = f(std::mem::replace(value, CoolDataType::Null));
+        }
+    }
+
+    pub fn truncate(&mut self, len: usize) {
+        self.0.truncate(len);
+    }
+
+    /// Releases excess capacity here and in every nested object and list.
+    pub fn shrink_to_fit(&mut self) {
+        self.0.shrink_to_fit();
+        for value in self.0.iter_mut() {
+            value.shrink_to_fit();
+        }
+    }
+
     impl_at!(string_at, string_at_mut, String, String);
-    impl_at!(int_at, int_at_mut, Int, i32);
+    impl_at!(int_at, int_at_mut, Int, i64);
+    impl_at!(uint_at, uint_at_mut, UInt, u64);
+    #[cfg(feature = "bigint")]
+    impl_at!(bigint_at, bigint_at_mut, BigInt, num_bigint::BigInt);
     impl_at!(float_at, float_at_mut, Float, f32);
     impl_at!(bool_at, bool_at_mut, Bool, bool);
+    impl_at!(char_at, char_at_mut, Char, char);
+    #[cfg(feature = "base64")]
+    impl_at!(bytes_at, bytes_at_mut, Bytes, Vec<u8>);
     impl_at!(object_at, object_at_mut, Object, CoolDataObject);
     impl_at!(list_at, list_at_mut, List, CoolDataList);
+
+    impl_first_last!(first_string, last_string, String, String);
+    impl_first_last!(first_int, last_int, Int, i64);
+    impl_first_last!(first_uint, last_uint, UInt, u64);
+    impl_first_last!(first_float, last_float, Float, f32);
+    impl_first_last!(first_bool, last_bool, Bool, bool);
+    impl_first_last!(first_char, last_char, Char, char);
+    impl_first_last!(first_object, last_object, Object, CoolDataObject);
+    impl_first_last!(first_list, last_list, List, CoolDataList);
+}
+
+impl Default for CoolDataList {
+    fn default() -> Self {
+        Self::new()
+    }
 }
 
 impl Display for CoolDataList {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
-        for value in self.0.iter() {
-            writeln!(f, "{}", value)?;
+        write_list(f, self, &SerializeOptions::default())
+    }
+}
+
+/// Default limit on how deeply objects and lists may be nested.
+pub const DEFAULT_MAX_DEPTH: usize = 128;
+
+type ResolveFn<'a> = dyn FnMut(&str) -> Result<CoolDataObject> + 'a;
+
+/// Loads the object named by an `@include` directive.
+struct IncludeResolver<'a>(Box<ResolveFn<'a>>);
+
+impl std::fmt::Debug for IncludeResolver<'_> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "IncludeResolver")
+    }
+}
+
+/// A field parsed by [`Parser::parse_key_value`], with what the parser keeps
+/// about it besides the value.
+struct ParsedField {
+    name: String,
+    value: CoolDataType,
+    comment: Option<String>,
+    doc: Option<String>,
+    span: Option<(usize, usize)>,
+}
+
+impl ParsedField {
+    fn new(name: String, value: CoolDataType) -> Self {
+        Self {
+            name,
+            value,
+            comment: None,
+            doc: None,
+            span: None,
+        }
+    }
+
+    /// Stores the comment, doc and span on `out`.
+    fn annotate(&self, out: &mut CoolDataObject) {
+        if let Some(comment) = &self.comment {
+            out.set_field_comment(self.name.clone(), comment.clone());
+        }
+        if let Some(doc) = &self.doc {
+            out.set_field_doc(self.name.clone(), doc.clone());
+        }
+        if let Some(span) = self.span {
+            out.notes.entry(self.name.clone()).or_default().span = Some(span);
         }
-        Ok(())
     }
 }
 
 #[derive(Debug)]
-pub struct Parser {
-    tokens: Vec<Token>,
+pub struct Parser<'a> {
+    tokens: Vec<Token<'a>>,
     index: usize,
+    max_depth: usize,
+    structural: bool,
+    shrink: bool,
+    case_insensitive: bool,
+    comments: bool,
+    docs: bool,
+    spans: bool,
+    flags: bool,
+    source: Option<Cow<'a, str>>,
+    tab_width: usize,
+    includes: Option<IncludeResolver<'a>>,
 }
 
-impl Parser {
-    pub fn new(tokens: Vec<Token>) -> Self {
-        Self { tokens, index: 0 }
+impl<'a> Parser<'a> {
+    pub fn new(tokens: Vec<Token<'a>>) -> Self {
+        Self::with_max_depth(tokens, DEFAULT_MAX_DEPTH)
+    }
+
+    /// Creates a parser that errors once objects and lists nest deeper than `max_depth`.
+    pub fn with_max_depth(tokens: Vec<Token<'a>>, max_depth: usize) -> Self {
+        Self {
+            tokens,
+            index: 0,
+            max_depth,
+            structural: false,
+            shrink: false,
+            case_insensitive: false,
+            comments: false,
+            docs: false,
+            spans: false,
+            flags: false,
+            source: None,
+            tab_width: 1,
+            includes: None,
+        }
+    }
+
+    /// Releases excess capacity in the parsed result before returning it, for
+    /// configs that stay in memory for a long time. Off by default.
+    pub fn shrink_on_finish(mut self, enabled: bool) -> Self {
+        self.shrink = enabled;
+        self
+    }
+
+    /// Keeps keys as written but makes field lookups on every parsed object fall
+    /// back to case-insensitive matching, see [`CoolDataObject::set_case_insensitive`].
+    pub fn preserve_case_insensitive(mut self, enabled: bool) -> Self {
+        self.case_insensitive = enabled;
+        self
+    }
+
+    /// Keeps the `#` comment following a field's value, see
+    /// [`CoolDataObject::field_comment`]. Comments are discarded by default.
+    pub fn preserve_comments(mut self, enabled: bool) -> Self {
+        self.comments = enabled;
+        self
+    }
+
+    /// Keeps the `#` comment lines directly above a field as its documentation, see
+    /// [`CoolDataObject::field_doc`]. A blank line in between detaches them.
+    pub fn preserve_docs(mut self, enabled: bool) -> Self {
+        self.docs = enabled;
+        self
     }
 
-    fn peek(&self, offset: usize) -> Option<&Token> {
+    /// Records the byte range of every field's value, see
+    /// [`CoolDataObject::source_span`]. Needs tokens from the [`Tokenizer`].
+    pub fn record_spans(mut self, enabled: bool) -> Self {
+        self.spans = enabled;
+        self
+    }
+
+    /// Reads a bare key ending its line, like `verbose`, as `verbose = true`.
+    /// Off by default, where a key without `=` is an error.
+    pub fn flag_fields(mut self, enabled: bool) -> Self {
+        self.flags = enabled;
+        self
+    }
+
+    /// Whether the token at `offset` ends a flag field's line. In structural mode
+    /// there are no lines, so the start of the next field ends it too.
+    fn at_flag_end(&self, offset: usize) -> bool {
+        self.flags
+            && (matches!(
+                self.peek(offset),
+                None | Some(Token(
+                    TokenType::Newline | TokenType::Comment(_) | TokenType::RightBrace,
+                    _
+                ))
+            ) || self.structural && self.at_field_start(offset))
+    }
+
+    /// Whether a field or directive starts at the token at `offset`, judged by
+    /// delimiters alone for structural mode.
+    fn at_field_start(&self, offset: usize) -> bool {
+        match self.peek(offset) {
+            Some(Token(TokenType::Directive(_) | TokenType::DocumentEnd, _)) => true,
+            Some(Token(TokenType::Ident(_) | TokenType::String(_), _)) => matches!(
+                self.peek(offset + 1),
+                Some(Token(TokenType::Equals | TokenType::Colon, _))
+            ),
+            _ => false,
+        }
+    }
+
+    /// Handles top-level `@include "path"` directives by merging the fields of the
+    /// object `resolve` returns for the path, overriding fields defined above the
+    /// directive. Without a resolver, `@include` is an error.
+    pub fn with_include_resolver(
+        mut self,
+        resolve: impl FnMut(&str) -> Result<CoolDataObject> + 'a,
+    ) -> Self {
+        self.includes = Some(IncludeResolver(Box::new(resolve)));
+        self
+    }
+
+    /// Attaches the text the tokens came from, so errors quote the offending line.
+    /// Without it errors stay terse.
+    pub fn with_source(mut self, source: impl Into<Cow<'a, str>>) -> Self {
+        self.source = Some(source.into());
+        self
+    }
+
+    /// The [`TokenizerOptions::tab_width`] the tokens were lexed with, so the
+    /// carets in quoted source lines sit under the right characters. Defaults to 1.
+    ///
+    /// [`TokenizerOptions::tab_width`]: crate::lexer::TokenizerOptions::tab_width
+    pub fn tab_width(mut self, width: usize) -> Self {
+        self.tab_width = width;
+        self
+    }
+
+    /// In structural mode newlines carry no meaning: fields and list elements are
+    /// delimited only by keys, `=`, commas and brackets, so layout is free-form.
+    pub fn structural_mode(mut self, enabled: bool) -> Self {
+        self.structural = enabled;
+        self
+    }
+
+    fn peek(&self, offset: usize) -> Option<&Token<'a>> {
         self.tokens.get(self.index + offset)
     }
 
-    fn consume(&mut self) -> Result<&Token> {
+    fn consume(&mut self) -> Result<&Token<'a>> {
         let t = self
             .tokens
             .get(self.index)
@@ -206,203 +1456,546 @@ impl Parser {
         t
     }
 
-    fn parse_list(&mut self) -> Result<CoolDataList> {
+    fn error_at(&self, kind: ErrorKind, message: String, span: &Span) -> Error {
+        let message = match &self.source {
+            Some(source) => format!(
+                "{} at {}\n{}",
+                message,
+                span,
+                span.snippet(source, self.tab_width)
+            ),
+            None => format!("{} at {}", message, span),
+        };
+        CoolError::Parse {
+            kind,
+            message,
+            loc: span.start.clone(),
+        }
+        .into()
+    }
+
+    /// An error for the token at the cursor, which isn't what the caller expected.
+    fn unexpected(&self, expected: &str) -> Error {
+        match self.peek(0) {
+            Some(Token(token_type, span)) => self.error_at(
+                ErrorKind::InvalidData,
+                format!("Expected {}, found `{}`", expected, token_type),
+                span,
+            ),
+            None => self.eof_error(format!("Expected {}, found the end of input", expected)),
+        }
+    }
+
+    /// An `UnexpectedEof` error located just past the last token.
+    fn eof_error(&self, message: String) -> Error {
+        let loc = match self.tokens.last() {
+            Some(Token(_, span)) => span.end.clone(),
+            None => Loc::new(1, 1),
+        };
+        CoolError::Parse {
+            kind: ErrorKind::UnexpectedEof,
+            message,
+            loc,
+        }
+        .into()
+    }
+
+    fn new_object(&self) -> CoolDataObject {
+        let mut object = CoolDataObject::new();
+        object.set_case_insensitive(self.case_insensitive);
+        object
+    }
+
+    fn check_depth(&self, depth: usize) -> Result<()> {
+        if depth > self.max_depth {
+            let Some(Token(_, span)) = self.tokens.get(self.index.saturating_sub(1)) else {
+                return Err(self.eof_error("End of tokens!".to_string()));
+            };
+            return Err(self.error_at(
+                ErrorKind::InvalidData,
+                format!("Maximum nesting depth of {} exceeded", self.max_depth),
+                span,
+            ));
+        }
+        Ok(())
+    }
+
+    fn parse_value(&mut self, depth: usize) -> Result<CoolDataType> {
+        let Some(Token(token_type, span)) = self.peek(0).cloned() else {
+            return Err(self.eof_error("End of tokens!".to_string()));
+        };
+
+        let value = match &token_type {
+            TokenType::LeftBrace => {
+                self.consume()?;
+                let val = self.parse_object(depth + 1)?;
+                let Some(Token(TokenType::RightBrace, _)) = self.peek(0) else {
+                    return Err(self.error_at(
+                        ErrorKind::UnexpectedEof,
+                        "Unclosed `{`".to_string(),
+                        &span,
+                    ));
+                };
+                CoolDataType::Object(val)
+            }
+            TokenType::LeftBracket => {
+                self.consume()?;
+                let val = self.parse_list(depth + 1)?;
+                let Some(Token(TokenType::RightBracket, _)) = self.peek(0) else {
+                    return Err(self.error_at(
+                        ErrorKind::UnexpectedEof,
+                        "Unclosed `[`".to_string(),
+                        &span,
+                    ));
+                };
+                CoolDataType::List(val)
+            }
+            TokenType::Int(val) => match val.strip_suffix('u') {
+                Some(digits) => CoolDataType::uint(digits)?,
+                None => CoolDataType::int_literal(val)?,
+            },
+            TokenType::Float(val) => CoolDataType::float(val)?,
+            TokenType::String(val) => CoolDataType::String(val.to_string()),
+            TokenType::Bool(val) => CoolDataType::Bool(*val),
+            TokenType::Char(val) => CoolDataType::Char(*val),
+            #[cfg(feature = "base64")]
+            TokenType::Bytes(val) => CoolDataType::Bytes(val.clone()),
+            TokenType::Null => CoolDataType::Null,
+            _ => return Err(self.unexpected("a value")),
+        };
+        self.consume()?;
+
+        Ok(value)
+    }
+
+    fn parse_field(&mut self, out: &mut CoolDataObject, depth: usize) -> Result<()> {
+        let field = self.parse_key_value(depth)?;
+        field.annotate(out);
+        out.add_field(field.name, field.value);
+        Ok(())
+    }
+
+    /// Parses a `key = value` field, with its trailing comment if comments are
+    /// being kept.
+    fn parse_key_value(&mut self, depth: usize) -> Result<ParsedField> {
+        let Some(Token(TokenType::Ident(name) | TokenType::String(name), key_span)) =
+            self.peek(0).cloned()
+        else {
+            return Err(self.unexpected("a key"));
+        };
+        let doc = self.docs.then(|| self.leading_doc(self.index)).flatten();
+        self.consume()?;
+
+        let Some(Token(TokenType::Equals | TokenType::Colon, _)) = self.peek(0) else {
+            if self.at_flag_end(0) {
+                return Ok(ParsedField {
+                    doc,
+                    ..ParsedField::new(name.into_owned(), CoolDataType::Bool(true))
+                });
+            }
+            let Some(Token(tt, span)) = self.peek(0) else {
+                return Err(parse_error(
+                    ErrorKind::UnexpectedEof,
+                    format!(
+                        "Expected `=` or `:` after field {:?} at {}, found end of input",
+                        name, key_span.end
+                    ),
+                    &key_span.end,
+                ));
+            };
+            return Err(self.error_at(
+                ErrorKind::InvalidData,
+                format!("Exptected `=` or `:`, got `{}`", tt),
+                span,
+            ));
+        };
+        let Token(_, assign_span) = self.consume()?.clone();
+        self.check_value_present(&name, &assign_span)?;
+
+        let start = self.index;
+        let value = self.parse_value(depth)?;
+        let mut field = ParsedField::new(name.into_owned(), value);
+        field.doc = doc;
+        if self.spans {
+            let Token(_, first) = &self.tokens[start];
+            let Token(_, last) = &self.tokens[self.index - 1];
+            field.span = Some((first.bytes.start, last.bytes.end));
+        }
+        if let Some(Token(TokenType::Comment(text), _)) = self.peek(0) {
+            if self.comments {
+                field.comment = Some(text.to_string());
+            }
+            self.consume()?;
+        }
+        Ok(field)
+    }
+
+    /// Joins the comments on the lines right above the token at `key`, which must
+    /// start its line. Stops at the first line that isn't only a comment.
+    fn leading_doc(&self, key: usize) -> Option<String> {
+        let starts_line = |i: usize| i == 0 || matches!(self.tokens[i - 1].0, TokenType::Newline);
+        let mut lines = Vec::new();
+        let mut i = key;
+        while i >= 2 && matches!(self.tokens[i - 1].0, TokenType::Newline) {
+            let Token(TokenType::Comment(text), _) = &self.tokens[i - 2] else {
+                break;
+            };
+            if !starts_line(i - 2) {
+                break;
+            }
+            lines.push(text.as_ref());
+            i -= 2;
+        }
+
+        if lines.is_empty() {
+            return None;
+        }
+        lines.reverse();
+        Some(lines.join("\n"))
+    }
+
+    /// Errors if the field `name`, assigned at `assign_span`, has nothing where its
+    /// value should be.
+    fn check_value_present(&self, name: &str, assign_span: &Span) -> Result<()> {
+        let found = match self.peek(0) {
+            None => {
+                return Err(parse_error(
+                    ErrorKind::UnexpectedEof,
+                    format!(
+                        "Expected a value for field {:?} at {}, found end of input",
+                        name, assign_span.end
+                    ),
+                    &assign_span.end,
+                ))
+            }
+            Some(Token(TokenType::Newline, _)) => "the end of the line".to_string(),
+            Some(Token(TokenType::Comment(_), _)) => "a comment".to_string(),
+            Some(Token(
+                tt @ (TokenType::RightBrace | TokenType::RightBracket | TokenType::Comma),
+                _,
+            )) => format!("`{}`", tt),
+            Some(_) => return Ok(()),
+        };
+        let Token(_, span) = self.peek(0).unwrap();
+        Err(self.error_at(
+            ErrorKind::InvalidData,
+            format!("Expected a value for field {:?}, found {}", name, found),
+            span,
+        ))
+    }
+
+    fn parse_directive(&mut self, out: &mut CoolDataObject) -> Result<()> {
+        let Some(Token(TokenType::Directive(name), span)) = self.peek(0).cloned() else {
+            return Err(self.unexpected("a directive"));
+        };
+        self.consume()?;
+        if name != "include" {
+            return Err(self.error_at(
+                ErrorKind::InvalidData,
+                format!("Unknown directive `@{}`", name),
+                &span,
+            ));
+        }
+        let Some(Token(TokenType::String(path), _)) = self.peek(0).cloned() else {
+            return Err(self.error_at(
+                ErrorKind::InvalidData,
+                "Expected a path after `@include`".to_string(),
+                &span,
+            ));
+        };
+        self.consume()?;
+
+        let Some(IncludeResolver(resolve)) = &mut self.includes else {
+            return Err(self.error_at(
+                ErrorKind::Unsupported,
+                "`@include` is only supported when loading from a file".to_string(),
+                &span,
+            ));
+        };
+        for (key, value) in resolve(&path)? {
+            out.add_field(key, value);
+        }
+        Ok(())
+    }
+
+    fn parse_list(&mut self, depth: usize) -> Result<CoolDataList> {
+        self.check_depth(depth)?;
         let mut out = CoolDataList::new();
 
         while self
             .peek(0)
             .is_some_and(|Token(tt, _)| !matches!(tt, &TokenType::RightBracket))
         {
-            let t = self.peek(0).unwrap().clone();
-            let Token(ref token_type, loc) = t;
-
-            if matches!(token_type, &TokenType::Comma) {
-                self.consume()?;
-                // continue;
-            }
+            let Token(token_type, span) = self.peek(0).unwrap();
 
-            match &token_type {
+            match token_type {
                 TokenType::Ident(_) => {
-                    return Err(Error::new(
+                    return Err(self.error_at(
                         ErrorKind::InvalidData,
-                        format!("Expected `]`, got `{}` at {}:{}", token_type, loc.1, loc.0),
+                        format!("Expected `]`, got `{}`", token_type),
+                        span,
                     ));
                 }
-                TokenType::LeftBrace => {
-                    self.consume()?;
-                    let obj = self.parse_object()?;
-                    let Token(TokenType::RightBrace, _) = self.consume()? else {
-                        return Err(Error::new(
-                            ErrorKind::InvalidData,
-                            format!("Exptected `}}`, got `{}` at {}:{}", t.0, loc.1, loc.0),
-                        ));
-                    };
-                    out.0.push(CoolDataType::Object(obj));
-                }
-                TokenType::LeftBracket => {
-                    self.consume()?;
-                    let list = self.parse_list()?;
-                    let Some(Token(TokenType::RightBracket, _)) = self.peek(0) else {
-                        return Err(Error::new(
-                            ErrorKind::InvalidData,
-                            format!("Exptected `]`, got `{}` at {}:{}", t.0, loc.1, loc.0),
-                        ));
-                    };
-                    out.0.push(CoolDataType::List(list));
-                }
-                TokenType::Int(val) => {
-                    out.0.push(CoolDataType::int(val)?);
+                TokenType::Comma | TokenType::Newline | TokenType::Comment(_) => {
                     self.consume()?;
                 }
-                TokenType::Float(val) => {
-                    out.0.push(CoolDataType::float(val)?);
-                    self.consume()?;
-                }
-                TokenType::Bool(val) => {
-                    out.0.push(CoolDataType::Bool(*val));
-                    self.consume()?;
-                }
-                TokenType::String(val) => {
-                    out.0.push(CoolDataType::String(val.to_string()));
-                    self.consume()?;
-                }
-                TokenType::Newline => {
-                    self.consume()?;
+                _ => {
+                    let value = self.parse_value(depth)?;
+                    out.0.push(value);
                 }
-                other => unreachable!("{:?}", other),
             }
         }
 
         Ok(out)
     }
 
-    fn parse_object(&mut self) -> Result<CoolDataObject> {
-        let mut out = CoolDataObject::new();
+    fn parse_object(&mut self, depth: usize) -> Result<CoolDataObject> {
+        self.check_depth(depth)?;
+        let mut out = self.new_object();
 
         while self
             .peek(0)
             .is_some_and(|Token(tt, _)| !matches!(tt, &TokenType::RightBrace))
         {
-            let t = self.peek(0).unwrap().clone();
-            let Token(ref token_type, loc) = t;
-
-            match &token_type {
-                TokenType::Ident(name) => {
+            let Token(token_type, span) = self.peek(0).unwrap();
+            match token_type {
+                TokenType::Ident(_) | TokenType::String(_) => self.parse_field(&mut out, depth)?,
+                TokenType::Newline | TokenType::Comment(_) => {
                     self.consume()?;
-                    let Some(Token(TokenType::Equals, _)) = self.peek(0) else {
-                        let Some(Token(tt, loc)) = self.peek(0) else {
-                            unreachable!();
-                        };
-                        return Err(Error::new(
-                            ErrorKind::InvalidData,
-                            format!("Exptected `=`, got `{}` at {}:{}", tt, loc.1, loc.0),
-                        ));
-                    };
-                    self.consume()?;
-
-                    if let Some(Token(TokenType::LeftBrace, _)) = self.peek(0) {
-                        self.consume()?;
-                        let val = self.parse_object()?;
-                        let Some(Token(TokenType::RightBrace, _)) = self.peek(0) else {
-                            return Err(Error::new(
-                                ErrorKind::InvalidData,
-                                format!("Exptected `}}`, got `{}` at {}:{}", t.0, loc.1, loc.0),
-                            ));
-                        };
-                        self.consume()?;
-                        out.add_field(name.clone(), CoolDataType::Object(val));
-                    } else if let Some(Token(TokenType::LeftBracket, _)) = self.peek(0) {
-                        self.consume()?;
-                        let val = self.parse_list()?;
-                        out.add_field(name.clone(), CoolDataType::List(val));
-                    } else {
-                        let Some(Token(token_type, _)) = self.peek(0) else {
-                            return Err(Error::new(ErrorKind::UnexpectedEof, "End of tokens!"));
-                        };
-                        let data_type = match &token_type {
-                            TokenType::Int(val) => CoolDataType::int(val.as_str())?,
-                            TokenType::Float(val) => CoolDataType::float(val.as_str())?,
-                            TokenType::String(val) => CoolDataType::String(val.to_string()),
-                            TokenType::Bool(val) => CoolDataType::Bool(*val),
-                            other => unreachable!("{:?}", other),
-                        };
-                        self.consume()?;
-                        out.add_field(name.clone(), data_type);
-                    }
                 }
-                TokenType::Newline => {
-                    self.consume()?;
+                other => {
+                    return Err(self.error_at(
+                        ErrorKind::InvalidData,
+                        format!("Expected a key or `}}`, found `{}`", other),
+                        span,
+                    ));
                 }
-                other => unreachable!("{:?}", other),
             }
         }
 
         Ok(out)
     }
 
+    fn prepare(&mut self) {
+        if self.structural {
+            self.tokens
+                .retain(|Token(tt, _)| !matches!(tt, TokenType::Newline));
+        }
+    }
+
+    fn finish(&self, out: &mut CoolDataObject) {
+        if self.shrink {
+            out.shrink_to_fit();
+        }
+    }
+
+    /// Skips the field that started at token `start` by moving past the end of
+    /// its line, treating lines inside braces or brackets as part of the field.
+    /// In structural mode it stops at the start of the next field instead.
+    fn recover(&mut self, start: usize) {
+        self.index = start;
+        let mut nesting = 0isize;
+        while let Some(Token(token_type, _)) = self.peek(0) {
+            if self.structural && nesting <= 0 && self.index > start && self.at_field_start(0) {
+                break;
+            }
+            match token_type {
+                TokenType::LeftBrace | TokenType::LeftBracket => nesting += 1,
+                TokenType::RightBrace | TokenType::RightBracket => nesting -= 1,
+                TokenType::Newline if nesting <= 0 => break,
+                _ => {}
+            }
+            self.index += 1;
+        }
+    }
+
+    /// Parses the fields up to the end of the tokens or the first `---` document
+    /// separator, which is left unconsumed. See [`Parser::parse_stream`] to parse
+    /// every document.
     pub fn parse(&mut self) -> Result<CoolDataObject> {
-        let mut out = CoolDataObject::new();
+        self.prepare();
+
+        let mut out = self.new_object();
+        while self
+            .peek(0)
+            .is_some_and(|Token(tt, _)| !matches!(tt, TokenType::DocumentEnd))
+        {
+            self.parse_statement(&mut out)?;
+        }
+
+        self.finish(&mut out);
+        Ok(out)
+    }
+
+    /// Parses concatenated documents separated by `---` lines into an object each.
+    /// A trailing separator doesn't produce an extra empty document.
+    pub fn parse_stream(&mut self) -> Result<Vec<CoolDataObject>> {
+        let mut documents = vec![self.parse()?];
+        while let Some(Token(TokenType::DocumentEnd, _)) = self.peek(0) {
+            self.index += 1;
+            self.skip_newlines();
+            if self.peek(0).is_none() {
+                break;
+            }
+            documents.push(self.parse()?);
+        }
+        Ok(documents)
+    }
+
+    /// Parses the fields straight into `existing`, such as an object of defaults.
+    /// A field `existing` already has is replaced by what `resolve` returns for the
+    /// key, the current value and the parsed one, like
+    /// [`CoolDataObject::merge_with`]. On error `existing` keeps the fields merged
+    /// so far.
+    pub fn parse_into(
+        &mut self,
+        existing: &mut CoolDataObject,
+        resolve: impl Fn(&str, &CoolDataType, &CoolDataType) -> CoolDataType,
+    ) -> Result<()> {
+        self.prepare();
+
+        while let Some(Token(token_type, _)) = self.peek(0) {
+            if !matches!(token_type, TokenType::Ident(_) | TokenType::String(_)) {
+                self.parse_statement(existing)?;
+                continue;
+            }
+
+            let field = self.parse_key_value(0)?;
+            field.annotate(existing);
+            match existing.fields.get_mut(&field.name) {
+                Some(current) => *current = resolve(&field.name, current, &field.value),
+                None => existing.add_field(field.name, field.value),
+            }
+        }
+
+        self.finish(existing);
+        Ok(())
+    }
+
+    /// Parses top-level fields until the first one that fails, instead of erroring.
+    /// Returns them with the index of the token parsing stopped at, which is the
+    /// number of tokens when everything parsed. The failed field is left out.
+    pub fn parse_partial(&mut self) -> (CoolDataObject, usize) {
+        self.prepare();
+
+        let mut out = self.new_object();
         while self.peek(0).is_some() {
-            let t = self.peek(0).unwrap().clone();
-            let Token(ref token_type, loc) = t;
+            let start = self.index;
+            if self.parse_statement(&mut out).is_err() {
+                self.index = start;
+                break;
+            }
+        }
 
-            match &token_type {
-                TokenType::Ident(name) => {
-                    self.consume()?;
-                    let Some(Token(TokenType::Equals, _)) = self.peek(0) else {
-                        let Some(Token(tt, loc)) = self.peek(0) else {
-                            unreachable!("{}:{}:{}", file!(), line!(), column!());
-                        };
-                        return Err(Error::new(
-                            ErrorKind::InvalidData,
-                            format!("Exptected `=`, got `{}` at {}:{}", tt, loc.1, loc.0),
-                        ));
-                    };
-                    self.consume()?;
+        self.finish(&mut out);
+        (out, self.index)
+    }
 
-                    if let Some(Token(TokenType::LeftBrace, _)) = self.peek(0) {
-                        self.consume()?;
-                        let val = self.parse_object()?;
-                        let Token(TokenType::RightBrace, _) = self.consume()? else {
-                            return Err(Error::new(
-                                ErrorKind::InvalidData,
-                                format!("Exptected `}}`, got `{}` at {}:{}", t.0, loc.1, loc.0),
-                            ));
-                        };
-                        self.consume()?;
-                        out.add_field(name.clone(), CoolDataType::Object(val));
-                    } else if let Some(Token(TokenType::LeftBracket, _)) = self.peek(0) {
-                        let val = self.parse_list()?;
-                        let Token(TokenType::RightBracket, _) = self.consume()? else {
-                            return Err(Error::new(
-                                ErrorKind::InvalidData,
-                                format!("Exptected `]`, got `{}` at {}:{}", t.0, loc.1, loc.0),
-                            ));
-                        };
-                        self.consume()?;
-                        out.add_field(name.clone(), CoolDataType::List(val));
-                    } else {
-                        let Some(Token(token_type, _)) = self.peek(0) else {
-                            return Err(Error::new(ErrorKind::UnexpectedEof, "End of tokens!"));
-                        };
-                        let data_type = match &token_type {
-                            TokenType::Int(val) => CoolDataType::int(val.as_str())?,
-                            TokenType::Float(val) => CoolDataType::float(val.as_str())?,
-                            TokenType::String(val) => CoolDataType::String(val.to_string()),
-                            TokenType::Bool(val) => CoolDataType::Bool(*val),
-                            other => unreachable!("{:?}", other),
-                        };
-                        self.consume()?;
-                        out.add_field(name.clone(), data_type);
-                    }
-                }
-                TokenType::Newline => {
-                    self.consume()?;
-                }
-                other => unreachable!("{:?}", other),
+    /// Index of the next token to parse.
+    pub fn position(&self) -> usize {
+        self.index
+    }
+
+    fn parse_statement(&mut self, out: &mut CoolDataObject) -> Result<()> {
+        let Some(Token(token_type, _)) = self.peek(0) else {
+            return Ok(());
+        };
+        match token_type {
+            TokenType::Ident(_) | TokenType::String(_) => self.parse_field(out, 0),
+            TokenType::Directive(_) => self.parse_directive(out),
+            TokenType::Newline | TokenType::Comment(_) => self.consume().map(|_| ()),
+            _ => Err(self.unexpected("a key or directive")),
+        }
+    }
+
+    /// Parses the tokens as a single value, such as one list element, ignoring
+    /// surrounding newlines.
+    pub fn parse_element(&mut self) -> Result<CoolDataType> {
+        self.prepare();
+        self.skip_newlines();
+        let mut value = self.parse_value(0)?;
+        self.skip_newlines();
+
+        if let Some(Token(token_type, span)) = self.peek(0) {
+            return Err(self.error_at(
+                ErrorKind::InvalidData,
+                format!("Unexpected `{}` after the value", token_type),
+                span,
+            ));
+        }
+        if self.shrink {
+            value.shrink_to_fit();
+        }
+        Ok(value)
+    }
+
+    /// Parses a whole document whose root may be any value. Documents starting
+    /// with a `key =` field or a directive, as well as empty ones, parse like
+    /// [`Parser::parse`] into an object; anything else must be a single value.
+    pub fn parse_document(&mut self) -> Result<CoolDataType> {
+        self.prepare();
+        self.skip_newlines();
+
+        let object_root = matches!(
+            (self.peek(0), self.peek(1)),
+            (None, _)
+                | (Some(Token(TokenType::Directive(_), _)), _)
+                | (
+                    Some(Token(TokenType::Ident(_) | TokenType::String(_), _)),
+                    Some(Token(TokenType::Equals | TokenType::Colon, _)),
+                )
+        ) || matches!(self.peek(0), Some(Token(TokenType::Ident(_), _)))
+            && self.at_flag_end(1);
+        if object_root {
+            self.parse().map(CoolDataType::Object)
+        } else {
+            self.parse_element()
+        }
+    }
+
+    fn skip_newlines(&mut self) {
+        while let Some(Token(TokenType::Newline | TokenType::Comment(_), _)) = self.peek(0) {
+            self.index += 1;
+        }
+    }
+
+    /// Parses as much as possible, collecting every error instead of stopping at
+    /// the first. A field that fails to parse is skipped up to the end of its line
+    /// and left out of the returned object.
+    pub fn parse_all(&mut self) -> (CoolDataObject, Vec<Error>) {
+        self.prepare();
+
+        let mut out = self.new_object();
+        let mut errors = Vec::new();
+        while let Some(Token(token_type, span)) = self.peek(0) {
+            let start = self.index;
+            let result = match token_type {
+                TokenType::Ident(_) | TokenType::String(_) => self.parse_field(&mut out, 0),
+                TokenType::Directive(_) => self.parse_directive(&mut out),
+                TokenType::Newline | TokenType::Comment(_) => self.consume().map(|_| ()),
+                other => Err(self.error_at(
+                    ErrorKind::InvalidData,
+                    format!("Unexpected `{}`", other),
+                    span,
+                )),
+            };
+            if let Err(err) = result {
+                errors.push(err);
+                self.recover(start);
             }
         }
 
-        Ok(out)
+        self.finish(&mut out);
+        (out, errors)
+    }
+}
+
+/// A parser error for the token at `loc`, comparable through [`CoolError::of`].
+fn parse_error(kind: ErrorKind, message: String, loc: &Loc) -> Error {
+    CoolError::Parse {
+        kind,
+        message,
+        loc: loc.clone(),
     }
+    .into()
 }
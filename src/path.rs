@@ -0,0 +1,414 @@
+use std::{
+    collections::HashMap,
+    io::{Error, ErrorKind, Result},
+};
+
+use crate::parser::*;
+
+impl CoolDataType {
+    fn child(&self, segment: &str) -> Result<&CoolDataType> {
+        match self {
+            CoolDataType::Object(val) => val.get_field(segment),
+            CoolDataType::List(val) => {
+                let index = segment.parse().map_err(|_| {
+                    Error::new(
+                        ErrorKind::InvalidInput,
+                        format!("Expected a list index, got {:?}.", segment),
+                    )
+                })?;
+                val.at(index)
+            }
+            _ => Err(Error::new(
+                ErrorKind::InvalidInput,
+                format!("Cannot look up {:?} in a scalar value.", segment),
+            )),
+        }
+    }
+
+    /// Like `child`, but returns `None` on a miss instead of building an error.
+    fn try_child(&self, segment: &str) -> Option<&CoolDataType> {
+        match self {
+            CoolDataType::Object(val) => val.try_field(segment),
+            CoolDataType::List(val) => val.0.get(segment.parse::<usize>().ok()?),
+            _ => None,
+        }
+    }
+
+    fn try_child_mut(&mut self, segment: &str) -> Option<&mut CoolDataType> {
+        match self {
+            CoolDataType::Object(val) => val.get_field_mut(segment).ok(),
+            CoolDataType::List(val) => val.0.get_mut(segment.parse::<usize>().ok()?),
+            _ => None,
+        }
+    }
+}
+
+impl CoolDataObject {
+    /// Looks up a value by a dotted path such as `server.ports.0`, where numeric
+    /// segments index into lists. Keys that themselves contain a `.` can only be
+    /// reached with [`CoolDataObject::get_field`].
+    pub fn get_path(&self, path: &str) -> Result<&CoolDataType> {
+        let mut segments = path.split('.');
+        let first = segments.next().unwrap_or_default();
+        let mut value = self.get_field(first)?;
+        for segment in segments {
+            value = value.child(segment)?;
+        }
+        Ok(value)
+    }
+
+    /// Like [`CoolDataObject::get_path`], but returns `None` when any segment is
+    /// missing. Type checking is left to the caller.
+    pub fn try_path(&self, path: &str) -> Option<&CoolDataType> {
+        self.get_path(path).ok()
+    }
+
+    /// Whether the path resolves to a value, `null` included. Unlike
+    /// [`CoolDataObject::is_absent`] a miss doesn't allocate an error.
+    pub fn contains_path(&self, path: &str) -> bool {
+        let mut segments = path.split('.');
+        let first = segments.next().unwrap_or_default();
+        let Some(mut value) = self.try_field(first) else {
+            return false;
+        };
+        for segment in segments {
+            match value.try_child(segment) {
+                Some(child) => value = child,
+                None => return false,
+            }
+        }
+        true
+    }
+
+    /// Removes the value at a dotted path, leaving everything around it, even a
+    /// parent left empty, in place. Removing a list element shifts the ones after
+    /// it. Returns `None` if the path doesn't resolve.
+    pub fn remove_path(&mut self, path: &str) -> Option<CoolDataType> {
+        let Some((parent, leaf)) = path.rsplit_once('.') else {
+            return self.remove_field(path);
+        };
+        let mut segments = parent.split('.');
+        let first = segments.next().unwrap_or_default();
+        let mut value = self.get_field_mut(first).ok()?;
+        for segment in segments {
+            value = value.try_child_mut(segment)?;
+        }
+
+        match value {
+            CoolDataType::Object(val) => val.remove_field(leaf),
+            CoolDataType::List(val) => {
+                let index = leaf.parse::<usize>().ok()?;
+                (index < val.0.len()).then(|| val.0.remove(index))
+            }
+            _ => None,
+        }
+    }
+
+    /// Whether the path resolves to an explicit `null`.
+    pub fn is_present_and_null(&self, path: &str) -> bool {
+        matches!(self.get_path(path), Ok(CoolDataType::Null))
+    }
+
+    /// Whether the path doesn't resolve to any value, not even `null`.
+    pub fn is_absent(&self, path: &str) -> bool {
+        self.get_path(path).is_err()
+    }
+
+    /// Gets the entry at a dotted path for in-place insert-or-update, like
+    /// `HashMap::entry`. Missing intermediate objects are created on insert;
+    /// an intermediate that exists but isn't an object is an error.
+    pub fn path_entry(&mut self, path: &str) -> Result<PathEntry<'_>> {
+        let segments: Vec<String> = path.split('.').map(String::from).collect();
+        let mut current: &CoolDataObject = self;
+        for segment in &segments[..segments.len() - 1] {
+            match current.get_field(segment) {
+                Ok(CoolDataType::Object(val)) => current = val,
+                Ok(_) => {
+                    return Err(Error::new(
+                        ErrorKind::InvalidInput,
+                        format!("Field {:?} in path {:?} is not an object.", segment, path),
+                    ))
+                }
+                Err(_) => break,
+            }
+        }
+        Ok(PathEntry {
+            object: self,
+            segments,
+        })
+    }
+
+    /// Sets the value at a dotted path, creating missing intermediate objects.
+    /// Errors if an intermediate segment exists but isn't an object.
+    pub fn set_path(&mut self, path: &str, value: CoolDataType) -> Result<()> {
+        *self.path_entry(path)?.or_insert(CoolDataType::Null) = value;
+        Ok(())
+    }
+
+    /// Precomputes every path in the object so repeated lookups don't re-walk it.
+    ///
+    /// The index borrows the object immutably, so it can't go stale: the object
+    /// can't be modified until the index is dropped.
+    pub fn path_index(&self) -> PathIndex<'_> {
+        let mut entries = HashMap::new();
+        for (key, value) in self.iter() {
+            index_value(&mut entries, key.clone(), value);
+        }
+        PathIndex { entries }
+    }
+
+    /// Calls `f` on every scalar leaf with its path segments, recursing into
+    /// nested objects and lists. List indices appear as their numeric string.
+    pub fn walk<F: FnMut(&[String], &CoolDataType)>(&self, mut f: F) {
+        let mut path = Vec::new();
+        for (key, value) in self.iter() {
+            path.push(key.clone());
+            walk_value(&mut path, value, &mut f);
+            path.pop();
+        }
+    }
+
+    /// Like [`CoolDataObject::walk`], but allows modifying the leaves in place.
+    pub fn walk_mut<F: FnMut(&[String], &mut CoolDataType)>(&mut self, mut f: F) {
+        let mut path = Vec::new();
+        walk_object_mut(&mut path, self, &mut f);
+    }
+
+    /// Turns nested values into dotted keys, e.g. `{ a = { b = 1 } }` into
+    /// `{ "a.b" = 1 }`, with list elements indexed as `a.0`, `a.1`. Empty
+    /// objects and lists are kept as values.
+    pub fn flatten(&self) -> CoolDataObject {
+        let mut out = CoolDataObject::new();
+        for (key, value) in self.iter() {
+            flatten_value(&mut out, key.clone(), value);
+        }
+        out
+    }
+
+    /// The inverse of [`CoolDataObject::flatten`]. Segments numbered `0..n` become
+    /// a list. Errors if a key is both a value and a prefix of another key.
+    pub fn unflatten(&self) -> Result<CoolDataObject> {
+        let mut root = Vec::new();
+        for (key, value) in self.iter() {
+            let segments: Vec<&str> = key.split('.').collect();
+            insert_node(&mut root, key, &segments, value.clone())?;
+        }
+        Ok(build_object(root))
+    }
+}
+
+fn flatten_value(out: &mut CoolDataObject, path: String, value: &CoolDataType) {
+    match value {
+        CoolDataType::Object(val) if val.iter().next().is_some() => {
+            for (key, child) in val.iter() {
+                flatten_value(out, format!("{}.{}", path, key), child);
+            }
+        }
+        CoolDataType::List(val) if !val.0.is_empty() => {
+            for (index, child) in val.0.iter().enumerate() {
+                flatten_value(out, format!("{}.{}", path, index), child);
+            }
+        }
+        _ => out.add_field(path, value.clone()),
+    }
+}
+
+enum Node {
+    Leaf(CoolDataType),
+    Branch(Vec<(String, Node)>),
+}
+
+fn insert_node(
+    nodes: &mut Vec<(String, Node)>,
+    key: &str,
+    segments: &[&str],
+    value: CoolDataType,
+) -> Result<()> {
+    let conflict = || {
+        Error::new(
+            ErrorKind::InvalidInput,
+            format!("Key {:?} conflicts with another key.", key),
+        )
+    };
+    let (segment, rest) = segments.split_first().unwrap();
+    let existing = nodes.iter_mut().find(|(name, _)| name == segment);
+
+    match (existing, rest.first()) {
+        (None, None) => nodes.push((segment.to_string(), Node::Leaf(value))),
+        (None, Some(_)) => {
+            let mut children = Vec::new();
+            insert_node(&mut children, key, rest, value)?;
+            nodes.push((segment.to_string(), Node::Branch(children)));
+        }
+        (Some((_, Node::Branch(children))), Some(_)) => insert_node(children, key, rest, value)?,
+        (Some(_), _) => return Err(conflict()),
+    }
+    Ok(())
+}
+
+fn build_value(node: Node) -> CoolDataType {
+    let children = match node {
+        Node::Leaf(value) => return value,
+        Node::Branch(children) => children,
+    };
+
+    let mut indices: Vec<Option<usize>> =
+        children.iter().map(|(name, _)| name.parse().ok()).collect();
+    indices.sort();
+    let is_list = indices
+        .iter()
+        .enumerate()
+        .all(|(i, index)| *index == Some(i));
+
+    if is_list {
+        let mut children = children;
+        children.sort_by_key(|(name, _)| name.parse::<usize>().unwrap());
+        let list = children.into_iter().map(|(_, node)| build_value(node));
+        CoolDataType::List(CoolDataList(list.collect()))
+    } else {
+        CoolDataType::Object(build_object(children))
+    }
+}
+
+fn build_object(nodes: Vec<(String, Node)>) -> CoolDataObject {
+    let mut out = CoolDataObject::new();
+    for (name, node) in nodes {
+        out.add_field(name, build_value(node));
+    }
+    out
+}
+
+fn walk_value<F: FnMut(&[String], &CoolDataType)>(
+    path: &mut Vec<String>,
+    value: &CoolDataType,
+    f: &mut F,
+) {
+    match value {
+        CoolDataType::Object(val) => {
+            for (key, child) in val.iter() {
+                path.push(key.clone());
+                walk_value(path, child, f);
+                path.pop();
+            }
+        }
+        CoolDataType::List(val) => {
+            for (index, child) in val.0.iter().enumerate() {
+                path.push(index.to_string());
+                walk_value(path, child, f);
+                path.pop();
+            }
+        }
+        _ => f(path, value),
+    }
+}
+
+fn walk_object_mut<F: FnMut(&[String], &mut CoolDataType)>(
+    path: &mut Vec<String>,
+    object: &mut CoolDataObject,
+    f: &mut F,
+) {
+    let keys: Vec<String> = object.iter().map(|(key, _)| key.clone()).collect();
+    for key in keys {
+        let value = object.get_field_mut(&key).unwrap();
+        path.push(key);
+        walk_value_mut(path, value, f);
+        path.pop();
+    }
+}
+
+fn walk_value_mut<F: FnMut(&[String], &mut CoolDataType)>(
+    path: &mut Vec<String>,
+    value: &mut CoolDataType,
+    f: &mut F,
+) {
+    match value {
+        CoolDataType::Object(val) => walk_object_mut(path, val, f),
+        CoolDataType::List(val) => {
+            for (index, child) in val.0.iter_mut().enumerate() {
+                path.push(index.to_string());
+                walk_value_mut(path, child, f);
+                path.pop();
+            }
+        }
+        _ => f(path, value),
+    }
+}
+
+fn index_value<'a>(
+    entries: &mut HashMap<String, &'a CoolDataType>,
+    path: String,
+    value: &'a CoolDataType,
+) {
+    match value {
+        CoolDataType::Object(val) => {
+            for (key, child) in val.iter() {
+                index_value(entries, format!("{}.{}", path, key), child);
+            }
+        }
+        CoolDataType::List(val) => {
+            for (index, child) in val.0.iter().enumerate() {
+                index_value(entries, format!("{}.{}", path, index), child);
+            }
+        }
+        _ => {}
+    }
+    entries.insert(path, value);
+}
+
+/// An insert-or-update handle on a dotted path, see [`CoolDataObject::path_entry`].
+pub struct PathEntry<'a> {
+    object: &'a mut CoolDataObject,
+    segments: Vec<String>,
+}
+
+impl<'a> PathEntry<'a> {
+    pub fn or_insert(self, default: CoolDataType) -> &'a mut CoolDataType {
+        self.or_insert_with(|| default)
+    }
+
+    pub fn or_insert_with(self, default: impl FnOnce() -> CoolDataType) -> &'a mut CoolDataType {
+        let (key, parents) = self.segments.split_last().unwrap();
+        let mut current = self.object;
+        for segment in parents {
+            let value = current
+                .entry(segment.clone())
+                .or_insert_with(|| CoolDataType::Object(CoolDataObject::new()));
+            let CoolDataType::Object(val) = value else {
+                unreachable!("checked by CoolDataObject::path_entry");
+            };
+            current = val;
+        }
+        current.entry(key.clone()).or_insert_with(default)
+    }
+
+    /// Applies `f` to the value if the path already exists.
+    pub fn and_modify(self, f: impl FnOnce(&mut CoolDataType)) -> Self {
+        let (key, parents) = self.segments.split_last().unwrap();
+        let mut current = Some(&mut *self.object);
+        for segment in parents {
+            current = match current.map(|object| object.get_field_mut(segment)) {
+                Some(Ok(CoolDataType::Object(val))) => Some(val),
+                _ => None,
+            };
+        }
+        if let Some(Ok(value)) = current.map(|object| object.get_field_mut(key)) {
+            f(value);
+        }
+        self
+    }
+}
+
+/// A prebuilt lookup table from dotted paths to values, see [`CoolDataObject::path_index`].
+#[derive(Debug)]
+pub struct PathIndex<'a> {
+    entries: HashMap<String, &'a CoolDataType>,
+}
+
+impl<'a> PathIndex<'a> {
+    pub fn get(&self, path: &str) -> Result<&'a CoolDataType> {
+        self.entries.get(path).copied().ok_or(Error::new(
+            ErrorKind::InvalidInput,
+            format!("Unknown path {:?}", path),
+        ))
+    }
+}
@@ -0,0 +1,110 @@
+use std::fmt::Display;
+
+use crate::parser::*;
+
+/// Expected type of a field in a [`Schema`].
+#[derive(Debug, Clone)]
+pub enum FieldKind {
+    Int,
+    Float,
+    String,
+    Bool,
+    Char,
+    List,
+    /// An object, itself checked against the nested schema.
+    Object(Schema),
+}
+
+impl FieldKind {
+    fn type_name(&self) -> &'static str {
+        match self {
+            FieldKind::Int => "int",
+            FieldKind::Float => "float",
+            FieldKind::String => "string",
+            FieldKind::Bool => "bool",
+            FieldKind::Char => "char",
+            FieldKind::List => "list",
+            FieldKind::Object(_) => "object",
+        }
+    }
+}
+
+/// A schema violation at a dotted path.
+#[derive(Debug, Clone, PartialEq)]
+pub struct ValidationError {
+    pub path: String,
+    pub message: String,
+}
+
+impl Display for ValidationError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}: {}", self.path, self.message)
+    }
+}
+
+/// Describes the fields an object must or may have and their types.
+#[derive(Debug, Clone, Default)]
+pub struct Schema {
+    fields: Vec<(String, FieldKind, bool)>,
+}
+
+impl Schema {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn required(mut self, name: impl Into<String>, kind: FieldKind) -> Self {
+        self.fields.push((name.into(), kind, true));
+        self
+    }
+
+    /// A field that may be absent, but must have the given type when present.
+    pub fn optional(mut self, name: impl Into<String>, kind: FieldKind) -> Self {
+        self.fields.push((name.into(), kind, false));
+        self
+    }
+
+    /// Checks `object` against the schema, reporting every violation rather than
+    /// just the first. Fields the schema doesn't mention are allowed.
+    pub fn validate(&self, object: &CoolDataObject) -> Result<(), Vec<ValidationError>> {
+        let mut errors = Vec::new();
+        self.validate_at(None, object, &mut errors);
+        if errors.is_empty() {
+            Ok(())
+        } else {
+            Err(errors)
+        }
+    }
+
+    fn validate_at(
+        &self,
+        prefix: Option<&str>,
+        object: &CoolDataObject,
+        errors: &mut Vec<ValidationError>,
+    ) {
+        for (name, kind, required) in self.fields.iter() {
+            let path = match prefix {
+                Some(prefix) => format!("{}.{}", prefix, name),
+                None => name.clone(),
+            };
+            let Ok(value) = object.get_field(name) else {
+                if *required {
+                    errors.push(ValidationError {
+                        path,
+                        message: "Missing required field".to_string(),
+                    });
+                }
+                continue;
+            };
+
+            if value.type_name() != kind.type_name() {
+                errors.push(ValidationError {
+                    path,
+                    message: format!("Expected {}, got {}", kind.type_name(), value.type_name()),
+                });
+            } else if let (FieldKind::Object(schema), CoolDataType::Object(val)) = (kind, value) {
+                schema.validate_at(Some(&path), val, errors);
+            }
+        }
+    }
+}
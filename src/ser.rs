@@ -0,0 +1,316 @@
+use std::{
+    fmt::{self, Result, Write},
+    io,
+};
+
+use crate::parser::*;
+
+/// Default for [`SerializeOptions::inline_width`].
+pub const DEFAULT_INLINE_WIDTH: usize = 60;
+
+/// How string values are quoted, see [`SerializeOptions::quote_style`]. There is
+/// no single-quoted style, since `'x'` is a char literal.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum QuoteStyle {
+    /// `"""` blocks for strings spanning several lines, when their text allows it,
+    /// and `"..."` for everything else.
+    #[default]
+    TripleForMultiline,
+    /// Always `"..."`, with line breaks escaped as `\n`.
+    Double,
+}
+
+/// Options controlling how documents are written out.
+#[derive(Debug, Clone)]
+pub struct SerializeOptions {
+    /// Inserts this character between every three integer digits, e.g. `1,000,000`.
+    /// The lexer doesn't accept grouped numbers, so grouped output is for display only.
+    pub group_digits: Option<char>,
+    /// Lists of only scalars are written on one line, like `[1, 2, 3]`, when that
+    /// takes at most this many characters. Other lists get a line per element.
+    /// `0` always breaks lists across lines.
+    pub inline_width: usize,
+    pub quote_style: QuoteStyle,
+}
+
+impl SerializeOptions {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn inline_width(mut self, width: usize) -> Self {
+        self.inline_width = width;
+        self
+    }
+
+    pub fn quote_style(mut self, style: QuoteStyle) -> Self {
+        self.quote_style = style;
+        self
+    }
+
+    pub fn group_digits(mut self, separator: char) -> Self {
+        self.group_digits = Some(separator);
+        self
+    }
+}
+
+impl Default for SerializeOptions {
+    fn default() -> Self {
+        Self {
+            group_digits: None,
+            inline_width: DEFAULT_INLINE_WIDTH,
+            quote_style: QuoteStyle::default(),
+        }
+    }
+}
+
+impl CoolDataObject {
+    /// Serializes the object like its `Display` impl, but following `options`.
+    pub fn to_string_with(&self, options: &SerializeOptions) -> String {
+        let mut out = String::new();
+        write_object(&mut out, self, options).unwrap();
+        out
+    }
+
+    /// Serializes the object straight into `writer`, without building the text
+    /// in memory first. Wrap unbuffered writers such as files in a `BufWriter`.
+    pub fn write_to<W: io::Write>(&self, writer: W, options: &SerializeOptions) -> io::Result<()> {
+        IoWriter::run(writer, |out| write_object(out, self, options))
+    }
+}
+
+impl CoolDataType {
+    /// Like [`CoolDataObject::write_to`], for a single value.
+    pub fn write_to<W: io::Write>(&self, writer: W, options: &SerializeOptions) -> io::Result<()> {
+        IoWriter::run(writer, |out| write_value(out, self, options))
+    }
+}
+
+/// Adapts an `io::Write` to the `fmt::Write` the serializer targets, keeping
+/// the underlying I/O error that `fmt::Error` can't carry.
+struct IoWriter<W> {
+    inner: W,
+    error: Option<io::Error>,
+}
+
+impl<W: io::Write> IoWriter<W> {
+    fn run(inner: W, f: impl FnOnce(&mut Self) -> Result) -> io::Result<()> {
+        let mut out = Self { inner, error: None };
+        match f(&mut out) {
+            Ok(()) => Ok(()),
+            Err(fmt::Error) => Err(out
+                .error
+                .unwrap_or_else(|| io::Error::other("Formatting failed"))),
+        }
+    }
+}
+
+impl<W: io::Write> Write for IoWriter<W> {
+    fn write_str(&mut self, s: &str) -> Result {
+        self.inner.write_all(s.as_bytes()).map_err(|err| {
+            self.error = Some(err);
+            fmt::Error
+        })
+    }
+}
+
+/// Counts the bytes written to it, failing once they pass `limit` or a line
+/// breaks, so measuring an inline rendering stops early and allocates nothing.
+struct Measure {
+    len: usize,
+    limit: usize,
+}
+
+impl Write for Measure {
+    fn write_str(&mut self, s: &str) -> Result {
+        self.len += s.len();
+        if self.len > self.limit || s.contains('\n') {
+            return Err(fmt::Error);
+        }
+        Ok(())
+    }
+}
+
+pub(crate) fn write_object(
+    out: &mut impl Write,
+    object: &CoolDataObject,
+    options: &SerializeOptions,
+) -> Result {
+    for (key, value) in object.iter() {
+        for line in object.field_doc(key).into_iter().flat_map(str::lines) {
+            writeln!(out, "# {}", line)?;
+        }
+        write!(out, "{} = ", Key(key))?;
+        write_value(out, value, options)?;
+        if let Some(comment) = object.field_comment(key) {
+            write!(out, " # {}", comment)?;
+        }
+        writeln!(out)?;
+    }
+    Ok(())
+}
+
+pub(crate) fn write_list(
+    out: &mut impl Write,
+    list: &CoolDataList,
+    options: &SerializeOptions,
+) -> Result {
+    if fits_inline(list, options) {
+        write!(out, "[")?;
+        for (i, value) in list.0.iter().enumerate() {
+            if i > 0 {
+                write!(out, ", ")?;
+            }
+            write_value(out, value, options)?;
+        }
+        return write!(out, "]");
+    }
+
+    writeln!(out, "[")?;
+    for value in list.0.iter() {
+        write_value(out, value, options)?;
+        writeln!(out, ",")?;
+    }
+    write!(out, "]")
+}
+
+/// Whether `list` only holds scalars and fits the inline width on one line.
+fn fits_inline(list: &CoolDataList, options: &SerializeOptions) -> bool {
+    if list.0.is_empty() {
+        return true;
+    }
+    if list
+        .0
+        .iter()
+        .any(|value| value.is_object() || value.is_list())
+    {
+        return false;
+    }
+
+    let mut measure = Measure {
+        len: 2,
+        limit: options.inline_width,
+    };
+    list.0.iter().enumerate().all(|(i, value)| {
+        (i == 0 || measure.write_str(", ").is_ok())
+            && write_value(&mut measure, value, options).is_ok()
+    }) && measure.len <= options.inline_width
+}
+
+pub(crate) fn write_value(
+    out: &mut impl Write,
+    value: &CoolDataType,
+    options: &SerializeOptions,
+) -> Result {
+    match value {
+        CoolDataType::Int(val) => write_number(out, val, options),
+        CoolDataType::UInt(val) => write_number(out, format_args!("{}u", val), options),
+        #[cfg(feature = "bigint")]
+        CoolDataType::BigInt(val) => write_number(out, val, options),
+        CoolDataType::Float(val) => write_number(out, Float(*val), options),
+        CoolDataType::String(val)
+            if options.quote_style == QuoteStyle::TripleForMultiline && fits_triple(val) =>
+        {
+            write!(out, "\"\"\"\n{}\"\"\"", val)
+        }
+        CoolDataType::String(val) => write_quoted(out, val),
+        CoolDataType::Object(val) => {
+            writeln!(out, "{{")?;
+            write_object(out, val, options)?;
+            write!(out, "}}")
+        }
+        CoolDataType::List(val) => write_list(out, val, options),
+        CoolDataType::Bool(val) => write!(out, "{}", val),
+        CoolDataType::Char(val) => write_delimited(out, val.encode_utf8(&mut [0; 4]), '\''),
+        #[cfg(feature = "base64")]
+        CoolDataType::Bytes(val) => {
+            use base64::Engine;
+            let encoded = base64::engine::general_purpose::STANDARD.encode(val);
+            write!(out, "b64\"{}\"", encoded)
+        }
+        CoolDataType::Null => write!(out, "null"),
+    }
+}
+
+/// Whether `text` spans lines and survives a `"""` block verbatim.
+fn fits_triple(text: &str) -> bool {
+    text.contains('\n') && !text.contains("\"\"\"") && !text.ends_with('"') && !text.contains('\r')
+}
+
+/// Writes `text` as a `"..."` string, escaping exactly what the lexer needs:
+/// quotes, backslashes, line breaks, tabs and NUL. Everything else, non-ASCII
+/// included, is written as is.
+pub(crate) fn write_quoted(out: &mut impl Write, text: &str) -> Result {
+    write_delimited(out, text, '"')
+}
+
+/// Like [`write_quoted`], between `quote` characters, escaping those instead.
+fn write_delimited(out: &mut impl Write, text: &str, quote: char) -> Result {
+    out.write_char(quote)?;
+    for c in text.chars() {
+        match c {
+            c if c == quote => {
+                out.write_char('\\')?;
+                out.write_char(quote)?
+            }
+            '\\' => out.write_str("\\\\")?,
+            '\n' => out.write_str("\\n")?,
+            '\r' => out.write_str("\\r")?,
+            '\t' => out.write_str("\\t")?,
+            '\0' => out.write_str("\\0")?,
+            c => out.write_char(c)?,
+        }
+    }
+    out.write_char(quote)
+}
+
+/// Formats a float as the shortest decimal that parses back to the same `f32`,
+/// so `0.1` stays `0.1` rather than showing its binary approximation. Whole
+/// numbers keep a `.0` so they read back as floats, and the special values are
+/// written `nan`, `inf` and `-inf`.
+struct Float(f32);
+
+impl fmt::Display for Float {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> Result {
+        let val = self.0;
+        if val.is_nan() {
+            write!(f, "nan")
+        } else if val.fract() == 0.0 && val.is_finite() {
+            write!(f, "{}.0", val)
+        } else {
+            // `Display` for floats prints the shortest round-tripping digits, and
+            // `inf`/`-inf` for the infinities.
+            write!(f, "{}", val)
+        }
+    }
+}
+
+fn write_number(
+    out: &mut impl Write,
+    number: impl fmt::Display,
+    options: &SerializeOptions,
+) -> Result {
+    let Some(separator) = options.group_digits else {
+        return write!(out, "{}", number);
+    };
+
+    let text = number.to_string();
+    let text = text.as_str();
+    let (sign, unsigned) = match text.strip_prefix('-') {
+        Some(rest) => ("-", rest),
+        None => ("", text),
+    };
+    let split = unsigned
+        .find(|c: char| !c.is_ascii_digit())
+        .unwrap_or(unsigned.len());
+    let (digits, rest) = unsigned.split_at(split);
+
+    write!(out, "{}", sign)?;
+    for (i, digit) in digits.chars().enumerate() {
+        if i > 0 && (digits.len() - i) % 3 == 0 {
+            out.write_char(separator)?;
+        }
+        out.write_char(digit)?;
+    }
+    write!(out, "{}", rest)
+}
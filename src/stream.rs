@@ -0,0 +1,207 @@
+use std::{
+    collections::VecDeque,
+    io::{BufReader, Bytes, Error, ErrorKind, Read, Result},
+};
+
+use crate::{lexer::Tokenizer, parser::*};
+
+/// Iterator over the elements of a top-level list read from `R`, see
+/// [`crate::parse_list_stream`]. Only the element being parsed is held in memory.
+pub struct ListStream<R: Read> {
+    bytes: Bytes<BufReader<R>>,
+    lookahead: VecDeque<char>,
+    started: bool,
+    closed: bool,
+    done: bool,
+}
+
+impl<R: Read> ListStream<R> {
+    pub fn new(reader: R) -> Self {
+        Self {
+            bytes: BufReader::new(reader).bytes(),
+            lookahead: VecDeque::new(),
+            started: false,
+            closed: false,
+            done: false,
+        }
+    }
+
+    fn read_char(&mut self) -> Result<Option<char>> {
+        let Some(first) = self.bytes.next().transpose()? else {
+            return Ok(None);
+        };
+        let width = match first {
+            0x00..=0x7F => 1,
+            0xC0..=0xDF => 2,
+            0xE0..=0xEF => 3,
+            _ => 4,
+        };
+        let mut buf = vec![first];
+        for _ in 1..width {
+            match self.bytes.next().transpose()? {
+                Some(byte) => buf.push(byte),
+                None => break,
+            }
+        }
+        let text = std::str::from_utf8(&buf)
+            .map_err(|err| Error::new(ErrorKind::InvalidData, err.to_string()))?;
+        Ok(text.chars().next())
+    }
+
+    fn peek(&mut self, offset: usize) -> Result<Option<char>> {
+        while self.lookahead.len() <= offset {
+            match self.read_char()? {
+                Some(c) => self.lookahead.push_back(c),
+                None => return Ok(None),
+            }
+        }
+        Ok(Some(self.lookahead[offset]))
+    }
+
+    fn consume(&mut self) -> Result<Option<char>> {
+        self.peek(0)?;
+        Ok(self.lookahead.pop_front())
+    }
+
+    fn skip_to_list(&mut self) -> Result<()> {
+        while let Some(c) = self.consume()? {
+            if c == '[' {
+                return Ok(());
+            }
+            if !c.is_whitespace() {
+                return Err(Error::new(
+                    ErrorKind::InvalidData,
+                    format!("Expected `[`, got {:?}", c),
+                ));
+            }
+        }
+        Err(Error::new(ErrorKind::UnexpectedEof, "Expected `[`"))
+    }
+
+    /// Checks that only whitespace and comments follow the closing `]`.
+    fn skip_trailing(&mut self) -> Result<()> {
+        while let Some(c) = self.consume()? {
+            if c == '#' {
+                while self.peek(0)?.is_some_and(|c| c != '\n') {
+                    self.consume()?;
+                }
+            } else if !c.is_whitespace() {
+                return Err(Error::new(
+                    ErrorKind::InvalidData,
+                    format!("Unexpected {:?} after the closing `]`", c),
+                ));
+            }
+        }
+        Ok(())
+    }
+
+    /// Collects the source text of the next element, or `None` once the list is closed.
+    fn next_element(&mut self) -> Result<Option<String>> {
+        let mut buf = String::new();
+        let mut depth = 0usize;
+
+        loop {
+            let Some(c) = self.consume()? else {
+                return Err(Error::new(ErrorKind::UnexpectedEof, "Unclosed `[`"));
+            };
+            match c {
+                '"' if self.peek(0)? == Some('"') && self.peek(1)? == Some('"') => {
+                    buf.push_str("\"\"\"");
+                    self.consume()?;
+                    self.consume()?;
+                    self.copy_string(&mut buf, '"', 3)?;
+                }
+                '"' | '\'' => {
+                    buf.push(c);
+                    self.copy_string(&mut buf, c, 1)?;
+                }
+                '#' => {
+                    while self.peek(0)?.is_some_and(|c| c != '\n') {
+                        self.consume()?;
+                    }
+                }
+                '{' | '[' => {
+                    depth += 1;
+                    buf.push(c);
+                }
+                ']' if depth == 0 => {
+                    self.closed = true;
+                    return Ok((!buf.trim().is_empty()).then_some(buf));
+                }
+                '}' | ']' => {
+                    depth = depth.saturating_sub(1);
+                    buf.push(c);
+                }
+                '\r' if depth == 0 && self.peek(0)? == Some('\n') => {}
+                ',' | '\n' if depth == 0 => {
+                    if !buf.trim().is_empty() {
+                        return Ok(Some(buf));
+                    }
+                    buf.clear();
+                }
+                _ => buf.push(c),
+            }
+        }
+    }
+
+    /// Copies a string's text up to and including the first run of `count`
+    /// `quote`s. Single-quote strings skip over escapes; `"""` ones have none.
+    fn copy_string(&mut self, buf: &mut String, quote: char, count: usize) -> Result<()> {
+        let mut run = 0;
+        while let Some(c) = self.consume()? {
+            buf.push(c);
+            if c == quote {
+                run += 1;
+                if run == count {
+                    return Ok(());
+                }
+                continue;
+            }
+            run = 0;
+            if c == '\\' && count == 1 {
+                if let Some(escaped) = self.consume()? {
+                    buf.push(escaped);
+                }
+            }
+        }
+        Err(Error::new(
+            ErrorKind::UnexpectedEof,
+            format!("Unterminated `{}` string", quote.to_string().repeat(count)),
+        ))
+    }
+
+    fn next_value(&mut self) -> Result<Option<CoolDataType>> {
+        if !self.started {
+            self.started = true;
+            self.skip_to_list()?;
+        }
+        let text = if self.closed {
+            None
+        } else {
+            self.next_element()?
+        };
+        let Some(text) = text else {
+            self.skip_trailing()?;
+            return Ok(None);
+        };
+        let tokens = Tokenizer::new(text.as_str()).tokenize()?;
+        let mut parser = Parser::new(tokens).with_source(text.as_str());
+        parser.parse_element().map(Some)
+    }
+}
+
+/// Yields parsed elements until the list closes, then an error if anything but
+/// whitespace and comments follows it. Iteration stops after the first error.
+impl<R: Read> Iterator for ListStream<R> {
+    type Item = Result<CoolDataType>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.done {
+            return None;
+        }
+
+        let value = self.next_value();
+        self.done |= !matches!(value, Ok(Some(_)));
+        value.transpose()
+    }
+}
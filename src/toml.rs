@@ -0,0 +1,125 @@
+use std::io::{Error, ErrorKind, Result};
+
+use crate::parser::*;
+
+impl CoolDataObject {
+    /// Renders the object as TOML: scalars map to TOML scalars, nested objects to
+    /// `[tables]` and lists to arrays, with objects inside lists as inline tables.
+    /// `null` has no TOML equivalent and is an error. TOML allows arrays of mixed
+    /// types, so those are written as-is.
+    pub fn to_toml_string(&self) -> Result<String> {
+        let mut out = String::new();
+        write_table(&mut out, None, self)?;
+        Ok(out)
+    }
+}
+
+fn write_table(out: &mut String, path: Option<&str>, object: &CoolDataObject) -> Result<()> {
+    for (key, value) in object.iter() {
+        if !value.is_object() {
+            let full_path = join(path, key);
+            out.push_str(&format!(
+                "{} = {}\n",
+                toml_key(key),
+                toml_value(&full_path, value)?
+            ));
+        }
+    }
+
+    for (key, value) in object.iter() {
+        if let CoolDataType::Object(val) = value {
+            let path = join(path, &toml_key(key));
+            if !out.is_empty() {
+                out.push('\n');
+            }
+            out.push_str(&format!("[{}]\n", path));
+            write_table(out, Some(&path), val)?;
+        }
+    }
+    Ok(())
+}
+
+fn join(path: Option<&str>, key: &str) -> String {
+    match path {
+        Some(path) => format!("{}.{}", path, key),
+        None => key.to_string(),
+    }
+}
+
+fn toml_key(key: &str) -> String {
+    let bare = !key.is_empty()
+        && key
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || c == '_' || c == '-');
+    if bare {
+        key.to_string()
+    } else {
+        toml_string(key)
+    }
+}
+
+fn toml_string(text: &str) -> String {
+    let mut out = String::from('"');
+    for c in text.chars() {
+        match c {
+            '"' => out.push_str("\\\""),
+            '\\' => out.push_str("\\\\"),
+            '\n' => out.push_str("\\n"),
+            '\t' => out.push_str("\\t"),
+            '\r' => out.push_str("\\r"),
+            c if c.is_control() => out.push_str(&format!("\\u{:04X}", c as u32)),
+            c => out.push(c),
+        }
+    }
+    out.push('"');
+    out
+}
+
+fn toml_value(path: &str, value: &CoolDataType) -> Result<String> {
+    Ok(match value {
+        CoolDataType::Int(val) => val.to_string(),
+        // TOML integers are signed 64-bit, larger ones fall through to the error.
+        CoolDataType::UInt(val) if *val <= i64::MAX as u64 => val.to_string(),
+        CoolDataType::Float(val) if val.is_nan() => "nan".to_string(),
+        CoolDataType::Float(val) if val.is_infinite() => {
+            if *val > 0.0 { "inf" } else { "-inf" }.to_string()
+        }
+        CoolDataType::Float(val) => format!("{:?}", val),
+        CoolDataType::String(val) => toml_string(val),
+        CoolDataType::Char(val) => toml_string(&val.to_string()),
+        CoolDataType::Bool(val) => val.to_string(),
+        CoolDataType::List(val) => {
+            let values = val
+                .0
+                .iter()
+                .enumerate()
+                .map(|(index, value)| toml_value(&format!("{}.{}", path, index), value))
+                .collect::<Result<Vec<_>>>()?;
+            format!("[{}]", values.join(", "))
+        }
+        CoolDataType::Object(val) => {
+            let fields = val
+                .iter()
+                .map(|(key, value)| {
+                    let value = toml_value(&format!("{}.{}", path, key), value)?;
+                    Ok(format!("{} = {}", toml_key(key), value))
+                })
+                .collect::<Result<Vec<_>>>()?;
+            if fields.is_empty() {
+                "{}".to_string()
+            } else {
+                format!("{{ {} }}", fields.join(", "))
+            }
+        }
+        other => {
+            return Err(Error::new(
+                ErrorKind::InvalidData,
+                format!(
+                    "TOML can't represent the {} at {:?}",
+                    other.type_name(),
+                    path
+                ),
+            ))
+        }
+    })
+}
@@ -0,0 +1,163 @@
+//! Traits for mapping Rust types to and from cool values, implemented by
+//! `#[derive(CoolDeserialize, CoolSerialize)]` with the `derive` feature.
+
+use std::io::{Error, ErrorKind, Result};
+
+use crate::parser::*;
+
+/// A struct that can be built from an object's fields.
+pub trait CoolDeserialize: Sized {
+    fn from_cool(object: &CoolDataObject) -> Result<Self>;
+}
+
+/// A struct that can be written out as an object's fields.
+pub trait CoolSerialize {
+    fn to_cool(&self) -> CoolDataObject;
+}
+
+/// A type that can be read from a single value.
+pub trait FromCool: Sized {
+    fn from_cool_value(value: &CoolDataType) -> Result<Self>;
+
+    /// Reads the field `name` of `object`, naming the field in errors.
+    fn from_cool_field(object: &CoolDataObject, name: &str) -> Result<Self> {
+        Self::from_cool_value(object.get_field(name)?)
+            .map_err(|err| Error::new(err.kind(), format!("Field {:?}: {}", name, err)))
+    }
+}
+
+/// A type that can be written as a single value.
+pub trait ToCool {
+    fn to_cool_value(&self) -> CoolDataType;
+
+    /// Adds the value to `object` as the field `name`.
+    fn to_cool_field(&self, object: &mut CoolDataObject, name: &str) {
+        object.add_field(name.to_string(), self.to_cool_value());
+    }
+}
+
+macro_rules! impl_scalar {
+    ($type:ty, $data_type:ident) => {
+        impl FromCool for $type {
+            fn from_cool_value(value: &CoolDataType) -> Result<Self> {
+                match value {
+                    CoolDataType::$data_type(val) => Ok(val.clone()),
+                    other => Err(mismatch(stringify!($data_type), other)),
+                }
+            }
+        }
+
+        impl ToCool for $type {
+            fn to_cool_value(&self) -> CoolDataType {
+                CoolDataType::$data_type(self.clone())
+            }
+        }
+    };
+}
+
+impl_scalar!(i64, Int);
+impl_scalar!(u64, UInt);
+#[cfg(feature = "bigint")]
+impl_scalar!(num_bigint::BigInt, BigInt);
+impl_scalar!(f32, Float);
+impl_scalar!(String, String);
+impl_scalar!(bool, Bool);
+impl_scalar!(char, Char);
+impl_scalar!(CoolDataObject, Object);
+impl_scalar!(CoolDataList, List);
+
+/// Reads an `Int` that fits in 32 bits.
+impl FromCool for i32 {
+    fn from_cool_value(value: &CoolDataType) -> Result<Self> {
+        match value {
+            CoolDataType::Int(val) => i32::try_from(*val).map_err(|_| {
+                Error::new(
+                    ErrorKind::InvalidInput,
+                    format!("Value {} is out of range for i32.", val),
+                )
+            }),
+            other => Err(mismatch("Int", other)),
+        }
+    }
+}
+
+impl ToCool for i32 {
+    fn to_cool_value(&self) -> CoolDataType {
+        CoolDataType::Int((*self).into())
+    }
+}
+
+fn mismatch(expected: &str, found: &CoolDataType) -> Error {
+    Error::new(
+        ErrorKind::InvalidInput,
+        format!(
+            "Expected {}, got {}.",
+            expected.to_lowercase(),
+            found.type_name()
+        ),
+    )
+}
+
+/// Unwraps an object for derived [`FromCool`] impls.
+#[doc(hidden)]
+pub fn expect_object(value: &CoolDataType) -> Result<&CoolDataObject> {
+    match value {
+        CoolDataType::Object(val) => Ok(val),
+        other => Err(mismatch("object", other)),
+    }
+}
+
+impl<T: FromCool> FromCool for Vec<T> {
+    fn from_cool_value(value: &CoolDataType) -> Result<Self> {
+        let CoolDataType::List(list) = value else {
+            return Err(mismatch("list", value));
+        };
+        list.0
+            .iter()
+            .enumerate()
+            .map(|(index, value)| {
+                T::from_cool_value(value)
+                    .map_err(|err| Error::new(err.kind(), format!("Index {}: {}", index, err)))
+            })
+            .collect()
+    }
+}
+
+impl<T: ToCool> ToCool for Vec<T> {
+    fn to_cool_value(&self) -> CoolDataType {
+        CoolDataType::List(CoolDataList(self.iter().map(T::to_cool_value).collect()))
+    }
+}
+
+/// `None` is read from an absent field or `null`, and written as an absent field.
+impl<T: FromCool> FromCool for Option<T> {
+    fn from_cool_value(value: &CoolDataType) -> Result<Self> {
+        match value {
+            CoolDataType::Null => Ok(None),
+            value => T::from_cool_value(value).map(Some),
+        }
+    }
+
+    fn from_cool_field(object: &CoolDataObject, name: &str) -> Result<Self> {
+        if object.is_absent(name) {
+            return Ok(None);
+        }
+        Self::from_cool_value(object.get_field(name)?)
+            .map_err(|err| Error::new(err.kind(), format!("Field {:?}: {}", name, err)))
+    }
+}
+
+impl<T: ToCool> ToCool for Option<T> {
+    fn to_cool_value(&self) -> CoolDataType {
+        match self {
+            Some(val) => val.to_cool_value(),
+            None => CoolDataType::Null,
+        }
+    }
+
+    fn to_cool_field(&self, object: &mut CoolDataObject, name: &str) {
+        if let Some(val) = self {
+            val.to_cool_field(object, name);
+        }
+    }
+}